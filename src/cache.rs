@@ -0,0 +1,138 @@
+use crate::stats::{Backend, Contribution};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// Bump whenever the layout of [`RepoEntry`] changes, so old caches are discarded.
+const VERSION: u32 = 2;
+
+/// How many queries are remembered per repository, e.g. the default view, team
+/// mode and `--languages`. The least recently used one is dropped beyond that.
+const QUERIES_PER_REPO: usize = 4;
+
+/// Matching commits per repository and query from previous runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StatsCache {
+    version: u32,
+    /// Most recently used query first.
+    pub repos: HashMap<PathBuf, Vec<RepoEntry>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepoEntry {
+    /// Fingerprint of the query the contributions were collected for.
+    pub query: String,
+    /// Commit ids (Git) or operation ids (jj) the history was walked from.
+    pub tips: Vec<String>,
+    pub contributions: Vec<Contribution>,
+    /// What read the history this run, `None` if it was answered from the cache.
+    #[serde(skip)]
+    pub backend: Option<Backend>,
+    /// Commits looked at this run.
+    #[serde(skip)]
+    pub visited: usize,
+}
+
+impl StatsCache {
+    /// Load the cache, starting from scratch if it's missing, unreadable or outdated.
+    pub fn load(path: &Path) -> Self {
+        File::open(path)
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, StatsCache>(BufReader::new(file)).ok())
+            .filter(|cache| cache.version == VERSION)
+            .unwrap_or_default()
+    }
+
+    /// The entry collected for `path` under the query with this fingerprint.
+    pub fn get(&self, path: &Path, query: &str) -> Option<&RepoEntry> {
+        self.repos
+            .get(path)?
+            .iter()
+            .find(|entry| entry.query == query)
+    }
+
+    /// Store `entry`, replacing the one for the same repository and query.
+    pub fn insert(&mut self, path: PathBuf, entry: RepoEntry) {
+        let entries = self.repos.entry(path).or_default();
+        entries.retain(|other| other.query != entry.query);
+        entries.insert(0, entry);
+        entries.truncate(QUERIES_PER_REPO);
+    }
+
+    pub fn save(&mut self, path: &Path) {
+        self.version = VERSION;
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(file) = File::create(path) {
+            let _ = serde_json::to_writer(BufWriter::new(file), self);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("cache").join("stats.json");
+
+        let mut cache = StatsCache::default();
+        cache.insert(
+            PathBuf::from("/repos/a"),
+            RepoEntry {
+                query: "q".to_string(),
+                tips: vec!["abc".to_string()],
+                contributions: Vec::new(),
+                ..Default::default()
+            },
+        );
+        cache.save(&path);
+
+        let loaded = StatsCache::load(&path);
+        assert_eq!(
+            loaded.get(Path::new("/repos/a"), "q").unwrap().tips,
+            vec!["abc"]
+        );
+        assert!(loaded.get(Path::new("/repos/a"), "other").is_none());
+    }
+
+    #[test]
+    fn test_keeps_recent_queries() {
+        let mut cache = StatsCache::default();
+        let repo = PathBuf::from("/repos/a");
+        for query in ["a", "b", "c", "d", "a", "e"] {
+            cache.insert(
+                repo.clone(),
+                RepoEntry {
+                    query: query.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let queries: Vec<&str> = cache.repos[&repo]
+            .iter()
+            .map(|e| e.query.as_str())
+            .collect();
+        assert_eq!(queries, vec!["e", "a", "d", "c"]);
+    }
+
+    #[test]
+    fn test_discards_other_versions() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("stats.json");
+        std::fs::write(
+            &path,
+            r#"{"version":1,"repos":{"/repos/a":{"query":"q","tips":[],"contributions":[]}}}"#,
+        )
+        .unwrap();
+
+        assert!(StatsCache::load(&path).repos.is_empty());
+    }
+}
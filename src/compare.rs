@@ -0,0 +1,184 @@
+use crate::stats::CommitCounts;
+use crate::summary::{self, Summary};
+use anyhow::{Result, anyhow};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::str::FromStr;
+
+/// An inclusive range of days, written as `START..END` (e.g. `2026-01-01..2026-03-31`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl Period {
+    pub fn days(&self) -> i64 {
+        (self.end - self.start).num_days() + 1
+    }
+}
+
+impl FromStr for Period {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (start, end) = s
+            .split_once("..")
+            .ok_or_else(|| anyhow!("Period '{}' must look like START..END", s))?;
+        let period = Period {
+            start: start.trim().parse()?,
+            end: end.trim().parse()?,
+        };
+        if period.start > period.end {
+            return Err(anyhow!("Period '{}' ends before it starts", s));
+        }
+        Ok(period)
+    }
+}
+
+/// What to compare against: the window before the current one, or two explicit periods.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Compare {
+    Previous,
+    Periods(Period, Period),
+}
+
+impl FromStr for Compare {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s == "previous" {
+            return Ok(Compare::Previous);
+        }
+        let (a, b) = s.split_once(',').ok_or_else(|| {
+            anyhow!("Expected 'previous' or two periods as START..END,START..END")
+        })?;
+        Ok(Compare::Periods(a.parse()?, b.parse()?))
+    }
+}
+
+impl Compare {
+    /// The earlier and the later period, given the `current` window.
+    pub fn periods(&self, current: Period) -> (Period, Period) {
+        match *self {
+            Compare::Previous => (
+                Period {
+                    start: current.start - Duration::days(current.days()),
+                    end: current.start - Duration::days(1),
+                },
+                current,
+            ),
+            Compare::Periods(a, b) if a.start <= b.start => (a, b),
+            Compare::Periods(a, b) => (b, a),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PeriodStats {
+    pub period: Period,
+    pub summary: Summary,
+    /// The weekday with the most commits, and how many.
+    pub busiest_weekday: Option<(Weekday, i32)>,
+}
+
+impl PeriodStats {
+    pub fn new(commits: &CommitCounts, period: Period) -> Self {
+        PeriodStats {
+            period,
+            summary: summary::summarize(commits, period.start, period.end),
+            busiest_weekday: busiest_weekday(commits, period),
+        }
+    }
+}
+
+fn busiest_weekday(commits: &CommitCounts, period: Period) -> Option<(Weekday, i32)> {
+    let mut by_weekday = [0; 7];
+    for (date, n) in commits {
+        if (period.start..=period.end).contains(date) {
+            by_weekday[date.weekday().num_days_from_monday() as usize] += n;
+        }
+    }
+    // Ties go to the earlier weekday
+    let (index, max) = by_weekday
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_, n)| **n)?;
+    (*max > 0).then(|| (Weekday::try_from(index as u8).unwrap_or(Weekday::Mon), *max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("previous".parse::<Compare>().unwrap(), Compare::Previous);
+        assert_eq!(
+            "2026-04-01..2026-06-30,2026-01-01..2026-03-31"
+                .parse::<Compare>()
+                .unwrap(),
+            Compare::Periods(
+                Period {
+                    start: day(4, 1),
+                    end: day(6, 30)
+                },
+                Period {
+                    start: day(1, 1),
+                    end: day(3, 31)
+                }
+            )
+        );
+        assert!("2026-04-01..2026-06-30".parse::<Compare>().is_err());
+        assert!("2026-06-30..2026-04-01".parse::<Period>().is_err());
+    }
+
+    #[test]
+    fn test_periods() {
+        let current = Period {
+            start: day(3, 11),
+            end: day(3, 20),
+        };
+        assert_eq!(
+            Compare::Previous.periods(current),
+            (
+                Period {
+                    start: day(3, 1),
+                    end: day(3, 10)
+                },
+                current
+            )
+        );
+
+        let q1: Period = "2026-01-01..2026-03-31".parse().unwrap();
+        let q2: Period = "2026-04-01..2026-06-30".parse().unwrap();
+        assert_eq!(Compare::Periods(q2, q1).periods(current), (q1, q2));
+    }
+
+    #[test]
+    fn test_period_stats() {
+        // 2026-03-02 is a Monday
+        let commits: CommitCounts = [
+            (day(3, 2), 2),
+            (day(3, 3), 2),
+            (day(3, 9), 1),
+            (day(3, 20), 9),
+        ]
+        .into_iter()
+        .collect();
+        let stats = PeriodStats::new(
+            &commits,
+            Period {
+                start: day(3, 1),
+                end: day(3, 10),
+            },
+        );
+        assert_eq!(stats.summary.total, 5);
+        assert_eq!(stats.summary.active_days, 3);
+        assert_eq!(stats.busiest_weekday, Some((Weekday::Mon, 3)));
+    }
+}
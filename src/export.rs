@@ -0,0 +1,189 @@
+use crate::reflog::Event;
+use crate::stats::{CommitCounts, LanguageCounts};
+use crate::summary::{Streak, Summary};
+use crate::svg;
+use chrono::{Duration, NaiveDate};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Colored graph and summary for reading in a terminal
+    #[default]
+    Terminal,
+    /// Daily counts, the summary, languages and reflog events as a JSON object
+    Json,
+    /// Only the daily counts, as comma-separated `date,commits` rows
+    Csv,
+    /// Only the daily counts, as tab-separated `date\tcommits` rows
+    Tsv,
+    /// The contribution graph as a standalone SVG image
+    Svg,
+}
+
+/// What `--format` writes out.
+pub struct Export<'a> {
+    pub email: &'a str,
+    pub commits: &'a CommitCounts,
+    pub summary: &'a Summary,
+    pub co_authored: i32,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    /// Only written as JSON.
+    pub languages: Option<&'a LanguageCounts>,
+    /// Counted reflog entries per event, only written as JSON.
+    pub events: Option<&'a HashMap<Event, i32>>,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    email: &'a str,
+    start: NaiveDate,
+    end: NaiveDate,
+    days: Vec<Day>,
+    summary: SummaryReport,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    languages: Option<&'a LanguageCounts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    events: Option<&'a HashMap<Event, i32>>,
+}
+
+#[derive(Serialize)]
+struct Day {
+    date: NaiveDate,
+    commits: i32,
+}
+
+#[derive(Serialize)]
+struct SummaryReport {
+    total: i32,
+    co_authored: i32,
+    active_days: i64,
+    total_days: i64,
+    active_percentage: f64,
+    mean_per_active_day: f64,
+    current_streak: Option<Streak>,
+    longest_streak: Option<Streak>,
+    busiest_day: Option<Day>,
+}
+
+/// Every day from `start` to `end` (both inclusive), including empty ones.
+fn days(commits: &CommitCounts, start: NaiveDate, end: NaiveDate) -> Vec<Day> {
+    let mut days = Vec::new();
+    let mut date = start;
+    while date <= end {
+        days.push(Day {
+            date,
+            commits: commits.get(&date).copied().unwrap_or(0),
+        });
+        date += Duration::days(1);
+    }
+    days
+}
+
+/// Write the daily counts between `start` and `end` in a machine-readable
+/// format, or the graph as an image. Only JSON carries the rest.
+pub fn write(out: &mut impl Write, format: Format, export: &Export) -> io::Result<()> {
+    let Export {
+        commits,
+        summary,
+        start,
+        end,
+        ..
+    } = *export;
+    let separator = match format {
+        Format::Terminal => return Ok(()),
+        Format::Json => {
+            let report = Report {
+                email: export.email,
+                start,
+                end,
+                days: days(commits, start, end),
+                languages: export.languages,
+                events: export.events,
+                summary: SummaryReport {
+                    total: summary.total,
+                    co_authored: export.co_authored,
+                    active_days: summary.active_days,
+                    total_days: summary.total_days,
+                    active_percentage: summary.active_percentage(),
+                    mean_per_active_day: summary.mean_per_active_day(),
+                    current_streak: summary.current_streak,
+                    longest_streak: summary.longest_streak,
+                    busiest_day: summary
+                        .busiest_day
+                        .map(|(date, commits)| Day { date, commits }),
+                },
+            };
+            serde_json::to_writer_pretty(&mut *out, &report)?;
+            return writeln!(out);
+        }
+        Format::Svg => return out.write_all(svg::render(commits, start, end).as_bytes()),
+        Format::Csv => ',',
+        Format::Tsv => '\t',
+    };
+
+    writeln!(out, "date{}commits", separator)?;
+    for day in days(commits, start, end) {
+        writeln!(out, "{}{}{}", day.date, separator, day.commits)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::summary::summarize;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, d).unwrap()
+    }
+
+    fn render(format: Format) -> String {
+        let commits: CommitCounts = [(day(2), 3), (day(3), 1)].into_iter().collect();
+        let summary = summarize(&commits, day(1), day(4));
+        let events = [(Event::Commit, 3), (Event::Rebase, 1)]
+            .into_iter()
+            .collect();
+        let export = Export {
+            email: "test@example.com",
+            commits: &commits,
+            summary: &summary,
+            co_authored: 1,
+            start: day(1),
+            end: day(4),
+            languages: None,
+            events: Some(&events),
+        };
+        let mut out = Vec::new();
+        write(&mut out, format, &export).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_csv_includes_empty_days() {
+        assert_eq!(
+            render(Format::Csv),
+            "date,commits\n2026-03-01,0\n2026-03-02,3\n2026-03-03,1\n2026-03-04,0\n"
+        );
+        assert!(render(Format::Tsv).starts_with("date\tcommits\n2026-03-01\t0\n"));
+    }
+
+    #[test]
+    fn test_json_report() {
+        let report: serde_json::Value = serde_json::from_str(&render(Format::Json)).unwrap();
+        assert_eq!(report["email"], "test@example.com");
+        assert_eq!(report["start"], "2026-03-01");
+        assert_eq!(report["days"].as_array().unwrap().len(), 4);
+        assert_eq!(report["days"][1]["commits"], 3);
+        assert_eq!(report["summary"]["total"], 4);
+        assert_eq!(report["summary"]["co_authored"], 1);
+        assert_eq!(report["summary"]["active_days"], 2);
+        assert_eq!(report["summary"]["longest_streak"]["days"], 2);
+        assert_eq!(report["summary"]["busiest_day"]["date"], "2026-03-02");
+        assert_eq!(report["events"]["rebase"], 1);
+        assert!(report.get("languages").is_none());
+    }
+}
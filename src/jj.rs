@@ -0,0 +1,951 @@
+use crate::cache::RepoEntry;
+use crate::stats::{Backend, Contribution, Query};
+use crate::status::{Operation, RepoStatus};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, FixedOffset, Local, SecondsFormat, Utc};
+use jj_lib::backend::{MillisSinceEpoch, Timestamp};
+use jj_lib::config::StackedConfig;
+use jj_lib::fileset::{FilePattern, FilesetExpression};
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::merged_tree::TreeDiffIterator;
+use jj_lib::repo::{ReadonlyRepo, Repo, StoreFactories};
+use jj_lib::repo_path::RepoPathUiConverter;
+use jj_lib::revset::{
+    self, ResolvedRevsetExpression, RevsetAliasesMap, RevsetDiagnostics, RevsetExpression,
+    RevsetExtensions, RevsetFilterPredicate, RevsetParseContext, RevsetWorkspaceContext,
+    SymbolResolver,
+};
+use jj_lib::settings::UserSettings;
+use jj_lib::str_util::{StringExpression, StringPattern};
+use jj_lib::time_util::DatePattern;
+use jj_lib::workspace::{Workspace, default_working_copy_factories};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration as StdDuration;
+use wait_timeout::ChildExt;
+
+/// Everything visible except empty commits (the fresh working copy, empty merges).
+pub const DEFAULT_REVSET: &str = "::visible_heads() ~ empty()";
+
+/// How to run the `jj` CLI for repos jj-lib can't load.
+#[derive(Debug, Clone)]
+pub struct CliOptions {
+    pub executable: PathBuf,
+    /// Kill `jj` if it hasn't finished by then, e.g. when stuck on a stale lock.
+    pub timeout: StdDuration,
+}
+
+impl Default for CliOptions {
+    fn default() -> Self {
+        CliOptions {
+            executable: PathBuf::from("jj"),
+            timeout: StdDuration::from_secs(30),
+        }
+    }
+}
+
+/// Only warn about a missing `jj` once, not for every repo.
+static WARNED_MISSING: AtomicBool = AtomicBool::new(false);
+
+pub fn process(path: &Path, query: &Query, cached: Option<&RepoEntry>) -> Result<RepoEntry> {
+    // Every jj operation replaces the op heads, so they work as tips for the cache.
+    // Unlike Git, any operation can rewrite existing commits, so once they've
+    // moved the whole window is walked again rather than just what's new.
+    let mut entry = RepoEntry {
+        tips: op_heads(path),
+        backend: Some(Backend::JjLib),
+        ..Default::default()
+    };
+    if let Some(cached) = cached
+        && !entry.tips.is_empty()
+        && cached.tips == entry.tips
+    {
+        return Ok(RepoEntry {
+            backend: None,
+            visited: 0,
+            ..cached.clone()
+        });
+    }
+
+    // We don't diff jj commits, so they can't be attributed to a language.
+    if query.lang.is_some() {
+        return Ok(entry);
+    }
+
+    let cutoff_date = query.cutoff();
+    let paths = query.path_globs(path);
+
+    // Repos written by a newer jj than the one we link against may not load,
+    // the jj CLI can still read those.
+    (entry.contributions, entry.visited) = match load(path) {
+        Ok((workspace, repo)) => walk_native(&workspace, &repo, query, &paths, cutoff_date)?,
+        Err(native) => {
+            entry.backend = Some(Backend::JjCli);
+            walk_cli(path, query, &paths, cutoff_date)
+                .with_context(|| format!("jj-lib couldn't load the repo ({:#})", native))?
+        }
+    };
+
+    Ok(entry)
+}
+
+fn load(path: &Path) -> Result<(Workspace, Arc<ReadonlyRepo>)> {
+    // We only read, so jj-lib's defaults are enough and the user's config doesn't matter
+    let settings = UserSettings::from_config(StackedConfig::with_defaults())?;
+    let workspace = Workspace::load(
+        &settings,
+        path,
+        &StoreFactories::default(),
+        &default_working_copy_factories(),
+    )?;
+    let repo = workspace.repo_loader().load_at_head()?;
+    Ok((workspace, repo))
+}
+
+fn walk_native(
+    workspace: &Workspace,
+    repo: &Arc<ReadonlyRepo>,
+    query: &Query,
+    paths: &[&str],
+    cutoff_date: DateTime<Utc>,
+) -> Result<(Vec<Contribution>, usize)> {
+    let expression = resolve_revset(workspace, repo, query, paths, cutoff_date)?;
+    let revset = expression.evaluate(repo.as_ref())?;
+
+    // Divergent and rewritten commits share a change id, count each change once
+    let mut seen = HashSet::new();
+    let mut contributions = Vec::new();
+    let mut visited = 0;
+    for ids in revset.commit_change_ids() {
+        let (commit_id, change_id) = ids?;
+        visited += 1;
+        if !seen.insert(change_id) {
+            continue;
+        }
+
+        let commit = repo.store().get_commit(&commit_id)?;
+        let author = commit.author();
+        let time = to_datetime(&author.timestamp)?;
+        contributions.extend(
+            query
+                .credits(&author.email, commit.description())
+                .into_iter()
+                .map(|credit| credit.contribution(time, HashMap::new())),
+        );
+    }
+
+    Ok((contributions, visited))
+}
+
+/// Parse the configured revset, restricted to commits authored within the window
+/// and, if `paths` isn't empty, to ones touching a matching file.
+fn resolve_revset(
+    workspace: &Workspace,
+    repo: &Arc<ReadonlyRepo>,
+    query: &Query,
+    paths: &[&str],
+    cutoff_date: DateTime<Utc>,
+) -> Result<Arc<ResolvedRevsetExpression>> {
+    let aliases_map = RevsetAliasesMap::new();
+    let extensions = RevsetExtensions::new();
+    let path_converter = RepoPathUiConverter::Fs {
+        cwd: workspace.workspace_root().to_path_buf(),
+        base: workspace.workspace_root().to_path_buf(),
+    };
+    let context = RevsetParseContext {
+        aliases_map: &aliases_map,
+        local_variables: HashMap::new(),
+        user_email: &query.email,
+        date_pattern_context: Local::now().into(),
+        default_ignored_remote: None,
+        use_glob_by_default: false,
+        extensions: &extensions,
+        workspace: Some(RevsetWorkspaceContext {
+            path_converter: &path_converter,
+            workspace_name: workspace.workspace_name(),
+        }),
+    };
+
+    let revset_str = query.jj_revset.as_deref().unwrap_or(DEFAULT_REVSET);
+    let mut diagnostics = RevsetDiagnostics::new();
+    let expression = revset::parse(&mut diagnostics, revset_str, &context)
+        .with_context(|| format!("Invalid jj revset: {}", revset_str))?;
+
+    let mut in_window = RevsetExpression::filter(RevsetFilterPredicate::AuthorDate(
+        DatePattern::AtOrAfter(MillisSinceEpoch(cutoff_date.timestamp_millis())),
+    ));
+    if !paths.is_empty() {
+        let patterns = paths
+            .iter()
+            .map(|glob| {
+                FilePattern::root_file_glob(glob)
+                    .map(FilesetExpression::pattern)
+                    .with_context(|| format!("Invalid path glob: {}", glob))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        in_window = in_window.intersection(&RevsetExpression::filter(RevsetFilterPredicate::File(
+            FilesetExpression::union_all(patterns),
+        )));
+    }
+    let symbol_resolver = SymbolResolver::new(repo.as_ref(), extensions.symbol_resolvers());
+    Ok(expression
+        .intersection(&in_window)
+        .resolve_user_expression(repo.as_ref(), &symbol_resolver)?)
+}
+
+fn to_datetime(timestamp: &Timestamp) -> Result<DateTime<FixedOffset>> {
+    let offset = FixedOffset::east_opt(timestamp.tz_offset * 60)
+        .ok_or_else(|| anyhow!("Invalid UTC offset"))?;
+    Ok(DateTime::from_timestamp_millis(timestamp.timestamp.0)
+        .ok_or_else(|| anyhow!("Invalid timestamp"))?
+        .with_timezone(&offset))
+}
+
+fn walk_cli(
+    path: &Path,
+    query: &Query,
+    paths: &[&str],
+    cutoff_date: DateTime<Utc>,
+) -> Result<(Vec<Contribution>, usize)> {
+    let mut revset = format!(
+        r#"({}) & author_date(after:"{}")"#,
+        query.jj_revset.as_deref().unwrap_or(DEFAULT_REVSET),
+        cutoff_date.to_rfc3339_opts(SecondsFormat::Secs, true)
+    );
+    if !paths.is_empty() {
+        let fileset: Vec<String> = paths
+            .iter()
+            .map(|glob| format!("root-glob:{:?}", glob))
+            .collect();
+        revset.push_str(&format!(" & files({})", fileset.join(" | ")));
+    }
+
+    // Use RFC 3339 timestamps so the author's UTC offset survives. The description
+    // goes last and records end in NUL, as descriptions span several lines.
+    let stdout = run_cli(
+        &query.jj_cli,
+        path,
+        &[
+            "log",
+            "--no-graph",
+            "-r",
+            &revset,
+            "-T",
+            r#"change_id ++ "|" ++ author.email() ++ "|" ++ author.timestamp().format("%Y-%m-%dT%H:%M:%S%:z") ++ "|" ++ description ++ "\0""#,
+        ],
+    )?;
+
+    let mut seen = HashSet::new();
+    let mut contributions = Vec::new();
+    let mut visited = 0;
+    for record in stdout.split('\0') {
+        let parts: Vec<&str> = record.trim_start().splitn(4, '|').collect();
+
+        if parts.len() < 4 {
+            continue;
+        }
+        visited += 1;
+        if !seen.insert(parts[0]) {
+            continue;
+        }
+
+        let commit_email = parts[1].trim();
+
+        let date_str = parts[2].trim();
+
+        match DateTime::parse_from_rfc3339(date_str) {
+            Ok(time) if time >= cutoff_date => {
+                contributions.extend(
+                    query
+                        .credits(commit_email, parts[3])
+                        .into_iter()
+                        .map(|credit| credit.contribution(time, HashMap::new())),
+                );
+            }
+
+            _ => {}
+        }
+    }
+
+    Ok((contributions, visited))
+}
+
+/// Run a read-only `jj` command in `path` and return its stdout.
+fn run_cli(cli: &CliOptions, path: &Path, args: &[&str]) -> Result<String> {
+    // Never page, prompt or snapshot the working copy: we're not interactive,
+    // and a snapshot can block on locks held by a running jj.
+    let spawned = Command::new(&cli.executable)
+        .args(["--no-pager", "--ignore-working-copy", "--color", "never"])
+        .args(args)
+        .current_dir(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if !WARNED_MISSING.swap(true, Ordering::Relaxed) {
+                eprintln!(
+                    "Warning: `{}` not found. Repositories jj-lib can't read are skipped, set jj_path in the config to point at jj.",
+                    cli.executable.display()
+                );
+            }
+            return Err(anyhow!("{} not found", cli.executable.display()));
+        }
+        Err(e) => return Err(e).context("Failed to run jj"),
+    };
+
+    // Drain the pipes while waiting, or a chatty jj would block on a full pipe
+    let mut stdout_pipe = child.stdout.take().expect("stdout is piped");
+    let mut stderr_pipe = child.stderr.take().expect("stderr is piped");
+    let stdout = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        stdout_pipe.read_to_end(&mut buf).map(|_| buf)
+    });
+    let stderr = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        stderr_pipe.read_to_end(&mut buf).map(|_| buf)
+    });
+
+    let Some(status) = child.wait_timeout(cli.timeout)? else {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(anyhow!("jj timed out after {:?}", cli.timeout));
+    };
+
+    let stdout = stdout.join().expect("stdout reader panicked")?;
+    let stderr = stderr.join().expect("stderr reader panicked")?;
+    if !status.success() {
+        return Err(anyhow!(
+            "jj command failed: {}",
+            String::from_utf8_lossy(&stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8(stdout)?)
+}
+
+/// The working-copy commit's changes and conflicts, as of the last snapshot.
+/// Snapshotting would write to the repo, so edits since the last `jj` command
+/// aren't seen, and jj tracks new files itself so nothing is untracked.
+pub fn status(path: &Path) -> Result<RepoStatus> {
+    let (workspace, repo) = load(path)?;
+    let wc_id = repo
+        .view()
+        .get_wc_commit_id(workspace.workspace_name())
+        .ok_or_else(|| anyhow!("The workspace has no working-copy commit"))?;
+    let wc = repo.store().get_commit(wc_id)?;
+
+    let tree = wc.tree();
+    let parent_tree = wc.parent_tree(repo.as_ref())?;
+    let changed = TreeDiffIterator::new(&parent_tree, &tree, &EverythingMatcher).count();
+
+    let view = repo.view();
+    let branch = std::iter::once(wc.id())
+        .chain(wc.parent_ids())
+        .find_map(|id| view.local_bookmarks_for_commit(id).next())
+        .map(|(name, _)| name.as_str().to_string());
+
+    Ok(RepoStatus {
+        path: path.to_path_buf(),
+        branch,
+        changed,
+        untracked: 0,
+        operation: tree.has_conflict().then_some(Operation::Conflict),
+    })
+}
+
+/// The author date of the newest commit by `email` if given, leaving out empty
+/// working-copy commits. When something was authored since `cutoff`, only those
+/// commits are looked at, so active repositories aren't read back to the root.
+pub fn last_commit(
+    path: &Path,
+    email: Option<&str>,
+    cutoff: DateTime<Utc>,
+) -> Result<Option<DateTime<FixedOffset>>> {
+    let (_workspace, repo) = load(path)?;
+    // Only diff the working copies, not every commit in the history
+    let empty =
+        RevsetExpression::filter(RevsetFilterPredicate::File(FilesetExpression::all())).negated();
+    let mut expression = RevsetExpression::visible_heads()
+        .ancestors()
+        .minus(&RevsetExpression::working_copies().intersection(&empty));
+    if let Some(email) = email {
+        expression = expression.intersection(&RevsetExpression::filter(
+            RevsetFilterPredicate::AuthorEmail(StringExpression::pattern(StringPattern::exact_i(
+                email,
+            ))),
+        ));
+    }
+    let recent = expression.intersection(&RevsetExpression::filter(
+        RevsetFilterPredicate::AuthorDate(DatePattern::AtOrAfter(MillisSinceEpoch(
+            cutoff.timestamp_millis(),
+        ))),
+    ));
+
+    let extensions = RevsetExtensions::new();
+    let symbol_resolver = SymbolResolver::new(repo.as_ref(), extensions.symbol_resolvers());
+    for expression in [recent, expression] {
+        let revset = expression
+            .resolve_user_expression(repo.as_ref(), &symbol_resolver)?
+            .evaluate(repo.as_ref())?;
+        // Commits come newest first, but timestamps needn't be ordered like the graph
+        let mut newest = None;
+        for id in revset.iter() {
+            let time = to_datetime(&repo.store().get_commit(&id?)?.author().timestamp)?;
+            if newest.is_none_or(|newest| time > newest) {
+                newest = Some(time);
+            }
+        }
+        if newest.is_some() {
+            return Ok(newest);
+        }
+    }
+    Ok(None)
+}
+
+fn op_heads(path: &Path) -> Vec<String> {
+    let heads = path.join(".jj").join("repo").join("op_heads").join("heads");
+    let mut ids: Vec<String> = std::fs::read_dir(heads)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::DAYS_IN_LAST_SIX_MONTHS;
+    use chrono::Duration;
+    use jj_lib::backend::{CommitId, CopyId, Signature, TreeValue};
+    use jj_lib::merge::Merge;
+    use jj_lib::merged_tree::MergedTreeBuilder;
+    use jj_lib::ref_name::WorkspaceName;
+    use jj_lib::repo_path::RepoPathBuf;
+    use tempfile::tempdir;
+
+    fn settings(email: &str) -> UserSettings {
+        let mut config = StackedConfig::with_defaults();
+        let mut layer = jj_lib::config::ConfigLayer::empty(jj_lib::config::ConfigSource::User);
+        layer.set_value("user.name", "Test User").unwrap();
+        layer.set_value("user.email", email).unwrap();
+        config.add_layer(layer);
+        UserSettings::from_config(config).unwrap()
+    }
+
+    fn signature(email: &str, time: DateTime<FixedOffset>) -> Signature {
+        Signature {
+            name: "Test User".to_string(),
+            email: email.to_string(),
+            timestamp: Timestamp {
+                timestamp: MillisSinceEpoch(time.timestamp_millis()),
+                tz_offset: time.offset().local_minus_utc() / 60,
+            },
+        }
+    }
+
+    /// A commit to create: its parent (an index into the commits created before
+    /// it, or the root), author and whether it changes a file. With `change_of`
+    /// it reuses an earlier commit's change id, making that change divergent.
+    struct TestCommit<'a> {
+        parent: Option<usize>,
+        email: &'a str,
+        time: DateTime<FixedOffset>,
+        empty: bool,
+        description: &'a str,
+        change_of: Option<usize>,
+    }
+
+    fn commit<'a>(
+        parent: Option<usize>,
+        email: &'a str,
+        time: DateTime<FixedOffset>,
+    ) -> TestCommit<'a> {
+        TestCommit {
+            parent,
+            email,
+            time,
+            empty: false,
+            description: "commit",
+            change_of: None,
+        }
+    }
+
+    /// Create a jj repo with `commits`, with the working copy on top of the last one.
+    fn init_repo(path: &Path, git_backend: bool, commits: &[TestCommit]) {
+        std::fs::create_dir_all(path).unwrap();
+        let settings = settings("jj@example.com");
+        let (_workspace, repo) = if git_backend {
+            Workspace::init_internal_git(&settings, path).unwrap()
+        } else {
+            Workspace::init_simple(&settings, path).unwrap()
+        };
+        let store = repo.store();
+
+        let mut tx = repo.start_transaction();
+        let mut ids: Vec<CommitId> = Vec::new();
+        for (i, spec) in commits.iter().enumerate() {
+            let (parent, parent_tree) = match spec.parent {
+                Some(p) => {
+                    let parent = store.get_commit(&ids[p]).unwrap();
+                    (ids[p].clone(), parent.tree())
+                }
+                None => (store.root_commit_id().clone(), store.empty_merged_tree()),
+            };
+            let tree = if spec.empty {
+                parent_tree
+            } else {
+                let path = RepoPathBuf::from_internal_string(format!("file{}", i)).unwrap();
+                let id =
+                    pollster::block_on(store.write_file(&path, &mut "content".as_bytes())).unwrap();
+                let mut builder = MergedTreeBuilder::new(parent_tree);
+                builder.set_or_remove(
+                    path,
+                    Merge::normal(TreeValue::File {
+                        id,
+                        executable: false,
+                        copy_id: CopyId::placeholder(),
+                    }),
+                );
+                builder.write_tree().unwrap()
+            };
+            let mut builder = tx
+                .repo_mut()
+                .new_commit(vec![parent], tree)
+                .set_author(signature(spec.email, spec.time))
+                .set_description(spec.description);
+            if let Some(c) = spec.change_of {
+                builder =
+                    builder.set_change_id(store.get_commit(&ids[c]).unwrap().change_id().clone());
+            }
+            let commit = builder.write().unwrap();
+            ids.push(commit.id().clone());
+        }
+        let head = store.get_commit(ids.last().unwrap()).unwrap();
+        tx.repo_mut()
+            .check_out(WorkspaceName::DEFAULT.to_owned(), &head)
+            .unwrap();
+        // Checking out abandons the initial working-copy commit
+        tx.repo_mut().rebase_descendants().unwrap();
+        tx.commit("test commits").unwrap();
+    }
+
+    #[test]
+    fn test_process_jj_native() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("jj-repo");
+        let email = "test@example.com";
+        let now = Utc::now().fixed_offset();
+        let old = now - Duration::days(DAYS_IN_LAST_SIX_MONTHS + 30);
+        init_repo(
+            &repo_path,
+            false,
+            &[
+                commit(None, email, old),
+                commit(Some(0), email, now),
+                commit(Some(1), "other@example.com", now),
+                commit(Some(2), email, now),
+            ],
+        );
+
+        let entry = process(&repo_path, &Query::new(email), None)?;
+        assert_eq!(entry.contributions.len(), 2);
+        assert!(!entry.tips.is_empty());
+
+        // Nothing changed, so the cached entry is returned as is
+        let cached = RepoEntry {
+            contributions: Vec::new(),
+            ..entry.clone()
+        };
+        let entry = process(&repo_path, &Query::new(email), Some(&cached))?;
+        assert!(entry.contributions.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_jj_git_backend() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("jj-git-repo");
+        let email = "test@example.com";
+        init_repo(
+            &repo_path,
+            true,
+            &[commit(None, email, Utc::now().fixed_offset())],
+        );
+
+        let entry = process(&repo_path, &Query::new(email), None)?;
+        assert_eq!(entry.contributions.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_status() -> Result<()> {
+        let dir = tempdir()?;
+        let email = "test@example.com";
+        let now = Utc::now().fixed_offset();
+
+        // A fresh working-copy commit on top of the last one has no changes
+        let repo_path = dir.path().join("jj-repo");
+        init_repo(&repo_path, false, &[commit(None, email, now)]);
+        assert!(!status(&repo_path)?.needs_attention());
+
+        // Editing the commit that adds a file makes its change uncommitted work
+        let (_workspace, repo) = load(&repo_path)?;
+        let wc_id = repo
+            .view()
+            .get_wc_commit_id(WorkspaceName::DEFAULT)
+            .unwrap();
+        let parent_id = repo.store().get_commit(wc_id)?.parent_ids()[0].clone();
+        let parent = repo.store().get_commit(&parent_id)?;
+        let mut tx = repo.start_transaction();
+        tx.repo_mut()
+            .edit(WorkspaceName::DEFAULT.to_owned(), &parent)?;
+        tx.repo_mut().rebase_descendants()?;
+        tx.commit("edit")?;
+
+        let dirty = status(&repo_path)?;
+        assert_eq!(dirty.changed, 1);
+        assert_eq!(dirty.operation, None);
+        assert!(dirty.needs_attention());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_commit() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("jj-repo");
+        let email = "test@example.com";
+        let now = Utc::now().fixed_offset();
+        let old = now - Duration::days(400);
+        init_repo(
+            &repo_path,
+            false,
+            &[
+                commit(None, email, old),
+                commit(Some(0), "other@example.com", now),
+            ],
+        );
+
+        // The empty working-copy commit on top doesn't count
+        let cutoff = (now - Duration::days(180)).to_utc();
+        assert_eq!(
+            last_commit(&repo_path, None, cutoff)?.map(|t| t.timestamp()),
+            Some(now.timestamp())
+        );
+        // Nothing of ours since the cutoff, so the older history is read too
+        assert_eq!(
+            last_commit(&repo_path, Some("Test@Example.com"), cutoff)?.map(|t| t.timestamp()),
+            Some(old.timestamp())
+        );
+        assert_eq!(
+            last_commit(&repo_path, Some("nobody@example.com"), cutoff)?,
+            None
+        );
+
+        // Only the fresh working copy is recent, and it's empty
+        let quiet_path = dir.path().join("jj-quiet");
+        init_repo(&quiet_path, false, &[commit(None, email, old)]);
+        assert_eq!(
+            last_commit(&quiet_path, None, cutoff)?.map(|t| t.timestamp()),
+            Some(old.timestamp())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_revset_covers_all_heads_without_empty_commits() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("jj-heads");
+        let email = "test@example.com";
+        let now = Utc::now().fixed_offset();
+        init_repo(
+            &repo_path,
+            false,
+            &[
+                commit(None, email, now),
+                // Work on another head, not an ancestor of @
+                commit(Some(0), email, now),
+                TestCommit {
+                    empty: true,
+                    ..commit(Some(0), email, now)
+                },
+            ],
+        );
+
+        let entry = process(&repo_path, &Query::new(email), None)?;
+        assert_eq!(entry.contributions.len(), 2);
+
+        let query = Query {
+            jj_revset: Some("::@".to_string()),
+            ..Query::new(email)
+        };
+        let entry = process(&repo_path, &query, None)?;
+        assert_eq!(entry.contributions.len(), 2);
+
+        let query = Query {
+            jj_revset: Some("::@ ~ empty()".to_string()),
+            ..Query::new(email)
+        };
+        let entry = process(&repo_path, &query, None)?;
+        assert_eq!(entry.contributions.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_divergent_change_counts_once() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("jj-divergent");
+        let email = "test@example.com";
+        let now = Utc::now().fixed_offset();
+        init_repo(
+            &repo_path,
+            false,
+            &[
+                commit(None, email, now),
+                // Another visible version of the same change, on its own head
+                TestCommit {
+                    change_of: Some(0),
+                    ..commit(None, email, now)
+                },
+                commit(Some(0), email, now),
+            ],
+        );
+
+        let entry = process(&repo_path, &Query::new(email), None)?;
+        assert_eq!(entry.visited, 3);
+        assert_eq!(entry.contributions.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_revset() {
+        let dir = tempdir().unwrap();
+        let repo_path = dir.path().join("jj-invalid");
+        init_repo(
+            &repo_path,
+            false,
+            &[commit(None, "test@example.com", Utc::now().fixed_offset())],
+        );
+
+        let query = Query {
+            jj_revset: Some("::(".to_string()),
+            ..Query::new("test@example.com")
+        };
+        let error = process(&repo_path, &query, None).unwrap_err();
+        assert!(format!("{:#}", error).contains("Invalid jj revset"));
+    }
+
+    #[cfg(unix)]
+    fn fake_jj(dir: &Path, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("fake-jj");
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cli_timeout() {
+        let dir = tempdir().unwrap();
+        let cli = CliOptions {
+            executable: fake_jj(dir.path(), "sleep 10"),
+            timeout: StdDuration::from_millis(200),
+        };
+
+        let start = std::time::Instant::now();
+        let error = run_cli(&cli, dir.path(), &["log"]).unwrap_err();
+        assert!(error.to_string().contains("timed out"));
+        assert!(start.elapsed() < StdDuration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cli_isolation_flags() {
+        let dir = tempdir().unwrap();
+        let cli = CliOptions {
+            executable: fake_jj(dir.path(), r#"echo "$@""#),
+            ..Default::default()
+        };
+
+        let stdout = run_cli(&cli, dir.path(), &["log"]).unwrap();
+        assert_eq!(
+            stdout.trim(),
+            "--no-pager --ignore-working-copy --color never log"
+        );
+    }
+
+    #[test]
+    fn test_cli_missing_executable() {
+        let dir = tempdir().unwrap();
+        let cli = CliOptions {
+            executable: dir.path().join("no-such-jj"),
+            ..Default::default()
+        };
+
+        let error = run_cli(&cli, dir.path(), &["log"]).unwrap_err();
+        assert!(error.to_string().contains("not found"));
+    }
+
+    #[test]
+    fn test_co_authored_commits() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("pairing");
+        let email = "test@example.com";
+        let now = Utc::now().fixed_offset();
+        init_repo(
+            &repo_path,
+            false,
+            &[
+                commit(None, email, now),
+                TestCommit {
+                    description: "Pair on it\n\nCo-authored-by: Test User <test@example.com>\n",
+                    ..commit(Some(0), "other@example.com", now)
+                },
+            ],
+        );
+
+        let query = Query::new(email);
+        let entry = process(&repo_path, &query, None)?;
+        let co_authored: Vec<bool> = entry.contributions.iter().map(|c| c.co_authored).collect();
+        assert_eq!(co_authored.len(), 2);
+        assert_eq!(co_authored.iter().filter(|c| **c).count(), 1);
+
+        let query = Query {
+            co_authors: false,
+            ..Query::new(email)
+        };
+        assert_eq!(process(&repo_path, &query, None)?.contributions.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_filters() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("filtered");
+        let email = "test@example.com";
+        let now = Utc::now().fixed_offset();
+        init_repo(
+            &repo_path,
+            false,
+            &[
+                TestCommit {
+                    description: "chore(deps): bump serde\n",
+                    ..commit(None, email, now)
+                },
+                TestCommit {
+                    description: "Add parser\n",
+                    ..commit(Some(0), email, now)
+                },
+            ],
+        );
+
+        let query = Query {
+            exclude_grep: Some(regex::Regex::new(r"^chore\(deps\)")?),
+            ..Query::new(email)
+        };
+        assert_eq!(process(&repo_path, &query, None)?.contributions.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_filters() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("jj-paths");
+        let email = "test@example.com";
+        let now = Utc::now().fixed_offset();
+        // Each commit adds `file<index>`
+        init_repo(
+            &repo_path,
+            false,
+            &[
+                commit(None, email, now),
+                commit(Some(0), email, now),
+                commit(Some(1), email, now),
+            ],
+        );
+
+        let query = Query {
+            paths: vec!["file[12]".parse()?],
+            ..Query::new(email)
+        };
+        assert_eq!(process(&repo_path, &query, None)?.contributions.len(), 2);
+
+        let query = Query {
+            paths: vec!["other-repo=file1".parse()?],
+            ..Query::new(email)
+        };
+        assert_eq!(process(&repo_path, &query, None)?.contributions.len(), 3);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cli_multiline_descriptions() -> Result<()> {
+        let dir = tempdir()?;
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, false);
+        let script = format!(
+            r#"printf 'a|test@example.com|{now}|one\0b|other@example.com|{now}|two|pipes\n\nCo-authored-by: T <test@example.com>\n\0'"#
+        );
+        let query = Query {
+            jj_cli: CliOptions {
+                executable: fake_jj(dir.path(), &script),
+                ..Default::default()
+            },
+            ..Query::new("test@example.com")
+        };
+
+        let cutoff_date = Utc::now() - Duration::days(1);
+        let (contributions, visited) = walk_cli(dir.path(), &query, &[], cutoff_date)?;
+        assert_eq!(visited, 2);
+        let co_authored: Vec<bool> = contributions.iter().map(|c| c.co_authored).collect();
+        assert_eq!(co_authored, vec![false, true]);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cli_divergent_change_counts_once() -> Result<()> {
+        let dir = tempdir()?;
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, false);
+        // Change `a` has two visible commits, listed apart from each other
+        let script = format!(
+            r#"printf 'a|test@example.com|{now}|one\0b|test@example.com|{now}|two\0a|test@example.com|{now}|one, reworded\0'"#
+        );
+        let query = Query {
+            jj_cli: CliOptions {
+                executable: fake_jj(dir.path(), &script),
+                ..Default::default()
+            },
+            ..Query::new("test@example.com")
+        };
+
+        let cutoff_date = Utc::now() - Duration::days(1);
+        let (contributions, visited) = walk_cli(dir.path(), &query, &[], cutoff_date)?;
+        assert_eq!(visited, 3);
+        assert_eq!(contributions.len(), 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use std::path::Path;
+
+/// File extensions (lowercase) and the language they belong to.
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("rs", "Rust"),
+    ("go", "Go"),
+    ("py", "Python"),
+    ("pyi", "Python"),
+    ("js", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("cjs", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("ts", "TypeScript"),
+    ("mts", "TypeScript"),
+    ("cts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cc", "C++"),
+    ("cpp", "C++"),
+    ("cxx", "C++"),
+    ("hh", "C++"),
+    ("hpp", "C++"),
+    ("hxx", "C++"),
+    ("cs", "C#"),
+    ("java", "Java"),
+    ("kt", "Kotlin"),
+    ("kts", "Kotlin"),
+    ("scala", "Scala"),
+    ("swift", "Swift"),
+    ("m", "Objective-C"),
+    ("mm", "Objective-C"),
+    ("rb", "Ruby"),
+    ("php", "PHP"),
+    ("dart", "Dart"),
+    ("ex", "Elixir"),
+    ("exs", "Elixir"),
+    ("erl", "Erlang"),
+    ("hs", "Haskell"),
+    ("ml", "OCaml"),
+    ("mli", "OCaml"),
+    ("clj", "Clojure"),
+    ("cljs", "Clojure"),
+    ("lua", "Lua"),
+    ("zig", "Zig"),
+    ("nix", "Nix"),
+    ("sh", "Shell"),
+    ("bash", "Shell"),
+    ("zsh", "Shell"),
+    ("fish", "Shell"),
+    ("ps1", "PowerShell"),
+    ("sql", "SQL"),
+    ("html", "HTML"),
+    ("htm", "HTML"),
+    ("css", "CSS"),
+    ("scss", "SCSS"),
+    ("sass", "SCSS"),
+    ("vue", "Vue"),
+    ("svelte", "Svelte"),
+    ("md", "Markdown"),
+    ("markdown", "Markdown"),
+    ("json", "JSON"),
+    ("yaml", "YAML"),
+    ("yml", "YAML"),
+    ("toml", "TOML"),
+    ("xml", "XML"),
+    ("proto", "Protocol Buffers"),
+    ("tf", "HCL"),
+    ("hcl", "HCL"),
+];
+
+/// Well-known file names that carry no (useful) extension.
+const FILENAMES: &[(&str, &str)] = &[
+    ("Makefile", "Makefile"),
+    ("GNUmakefile", "Makefile"),
+    ("Dockerfile", "Dockerfile"),
+    ("Containerfile", "Dockerfile"),
+    ("CMakeLists.txt", "CMake"),
+    ("Rakefile", "Ruby"),
+    ("Gemfile", "Ruby"),
+    ("Justfile", "Just"),
+    ("justfile", "Just"),
+];
+
+/// Classify a repository-relative path by its file name or extension.
+pub fn detect(path: &str) -> Option<&'static str> {
+    let path = Path::new(path);
+    let file_name = path.file_name()?.to_str()?;
+
+    if let Some((_, lang)) = FILENAMES.iter().find(|(name, _)| *name == file_name) {
+        return Some(lang);
+    }
+
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    EXTENSIONS
+        .iter()
+        .find(|(e, _)| *e == ext)
+        .map(|(_, lang)| *lang)
+}
+
+/// Resolve a user-supplied language (`rust`, `Rust` or `rs`) to its canonical name.
+pub fn lookup(name: &str) -> Option<&'static str> {
+    let needle = name.to_ascii_lowercase();
+    EXTENSIONS
+        .iter()
+        .chain(FILENAMES)
+        .find(|(ext, lang)| lang.to_ascii_lowercase() == needle || *ext == needle)
+        .map(|(_, lang)| *lang)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_by_extension() {
+        assert_eq!(detect("src/main.rs"), Some("Rust"));
+        assert_eq!(detect("web/App.TSX"), Some("TypeScript"));
+        assert_eq!(detect("include/util.hpp"), Some("C++"));
+        assert_eq!(detect("LICENSE"), None);
+        assert_eq!(detect("assets/logo.png"), None);
+    }
+
+    #[test]
+    fn test_detect_by_filename() {
+        assert_eq!(detect("Makefile"), Some("Makefile"));
+        assert_eq!(detect("docker/Dockerfile"), Some("Dockerfile"));
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup("rust"), Some("Rust"));
+        assert_eq!(lookup("RS"), Some("Rust"));
+        assert_eq!(lookup("c++"), Some("C++"));
+        assert_eq!(lookup("klingon"), None);
+    }
+}
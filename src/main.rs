@@ -1,33 +1,200 @@
-use clap::Parser;
+use chrono::{Duration, Local};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use colored::Colorize;
 use directories::UserDirs;
+use regex::Regex;
 use std::env;
 use std::fs;
+use std::io::{BufWriter, Write};
 use std::path::PathBuf;
 use std::time::Instant;
 
+mod cache;
+mod compare;
+mod export;
+mod jj;
+mod languages;
+mod orphans;
+mod reflog;
 mod scanner;
+mod stale;
+mod staleness;
 mod stats;
+mod status;
+mod summary;
+mod svg;
+mod team;
+#[cfg(test)]
+mod test_utils;
 mod ui;
+mod unpushed;
+
+/// How many repositories `--profile` lists.
+const PROFILE_TOP: usize = 10;
+
+/// Print a progress or timing line, keeping stdout clean for `--format` output.
+macro_rules! chatter {
+    ($quiet:expr, $($arg:tt)*) => {
+        if $quiet {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum View {
+    /// Calendar of daily contributions
+    #[default]
+    Grid,
+    /// Hour-of-day by weekday grid
+    Punchcard,
+}
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Folder to scan
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     folder: Option<PathBuf>,
 
     /// Email to filter by
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     email: Option<String>,
 
-    /// Force a rescan of the filesystem (ignoring cache)
-    #[arg(short, long)]
+    /// Force a rescan of the filesystem and of each repo's history (ignoring caches)
+    #[arg(short, long, global = true)]
     rescan: bool,
 
+    /// How to render the contributions
+    #[arg(long, value_enum, default_value_t)]
+    view: View,
+
+    /// Count Git activity from reachable commits, the HEAD reflog, or both
+    #[arg(long, value_enum, default_value_t, global = true)]
+    source: stats::ActivitySource,
+
+    /// Compare with the previous window, or two periods as START..END,START..END
+    #[arg(long, value_name = "PERIODS")]
+    compare: Option<compare::Compare>,
+
+    /// Print daily counts as JSON, CSV or TSV, or the graph as SVG. Only JSON
+    /// includes the summary, --languages and the --source events
+    #[arg(long, value_enum, default_value_t)]
+    format: export::Format,
+
+    /// Write the --format output to this file instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Show commits and changed lines per language
+    #[arg(long)]
+    languages: bool,
+
+    /// Only count commits touching files in this language (e.g. rust)
+    #[arg(long)]
+    lang: Option<String>,
+
+    /// Only count commits whose message matches this regex
+    #[arg(long, value_name = "REGEX", global = true)]
+    grep: Option<Regex>,
+
+    /// Don't count commits whose message matches this regex (e.g. '^(WIP|fixup!)')
+    #[arg(long, value_name = "REGEX", global = true)]
+    exclude_grep: Option<Regex>,
+
+    /// Only count commits touching a matching file, as GLOB or REPO=GLOB (repeatable)
+    #[arg(long = "path", value_name = "GLOB", global = true)]
+    paths: Vec<stats::PathFilter>,
+
+    /// Only process repositories whose path or directory name matches (repeatable)
+    #[arg(long, value_name = "GLOB", global = true)]
+    repo_include: Vec<String>,
+
+    /// Skip repositories whose path or directory name matches (repeatable)
+    #[arg(long, value_name = "GLOB", global = true)]
+    repo_exclude: Vec<String>,
+
+    /// Don't count commits that only credit you in a Co-authored-by trailer
+    #[arg(long, global = true)]
+    no_co_authors: bool,
+
     /// Enable verbose logging of timing performance
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     verbose: bool,
+
+    /// List repositories skipped for having no recent activity
+    #[arg(long, global = true)]
+    show_skipped: bool,
+
+    /// Exit with a non-zero status if any repository failed to process
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Number of threads to scan and process repositories with (default: all cores)
+    #[arg(short, long, value_name = "N", global = true)]
+    jobs: Option<usize>,
+
+    /// List the slowest repositories with their walk time, commits visited and backend
+    #[arg(long, global = true)]
+    profile: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Leaderboard of every author in the scanned repositories
+    Team(TeamArgs),
+    /// List your commits that no branch or tag reaches anymore
+    Orphans,
+    /// List repositories with uncommitted changes or an unfinished rebase or merge
+    Status,
+    /// List branches with unpushed commits of yours or without an upstream
+    Unpushed,
+    /// List repositories nobody has committed to in a while, with their size on disk
+    Stale(StaleArgs),
+}
+
+impl Command {
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Team(_) => "team",
+            Command::Orphans => "orphans",
+            Command::Status => "status",
+            Command::Unpushed => "unpushed",
+            Command::Stale(_) => "stale",
+        }
+    }
+
+    /// Whether the subcommand counts commits, so the commit filters apply.
+    fn counts_commits(&self) -> bool {
+        matches!(self, Command::Team(_))
+    }
+}
+
+#[derive(Args)]
+struct TeamArgs {
+    /// Show a contribution graph for each person
+    #[arg(long)]
+    heatmaps: bool,
+
+    /// Only list the N most active people
+    #[arg(long, value_name = "N")]
+    top: Option<usize>,
+}
+
+#[derive(Args)]
+struct StaleArgs {
+    /// How long without commits makes a repository stale
+    #[arg(long, value_name = "N", default_value_t = 6)]
+    months: u32,
+
+    /// Only look at your own commits
+    #[arg(long)]
+    mine: bool,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -44,6 +211,7 @@ fn main() -> anyhow::Result<()> {
     // Cache setup
     let cache_dir = home_dir.join(".cache").join("gitlocalstats");
     let cache_path = cache_dir.join("repos.json");
+    let stats_cache_path = cache_dir.join("stats.json");
 
     if !config_path.exists() {
         if let Some(parent) = config_path.parent() {
@@ -73,41 +241,339 @@ fn main() -> anyhow::Result<()> {
         .or_else(get_git_config_email)
         .unwrap_or_else(|| "example@email.com".to_string());
 
-    println!(
+    let lang = cli
+        .lang
+        .as_deref()
+        .map(|name| {
+            languages::lookup(name).ok_or_else(|| anyhow::anyhow!("Unknown language: {}", name))
+        })
+        .transpose()?;
+
+    // Filters on the command line replace the ones from the config
+    let paths = if cli.paths.is_empty() {
+        config_list("paths")
+            .iter()
+            .map(|filter| filter.parse())
+            .collect::<anyhow::Result<Vec<_>>>()?
+    } else {
+        cli.paths.clone()
+    };
+    let repo_filter = scanner::RepoFilter::new(
+        &or_config_list(&cli.repo_include, "repo_include"),
+        &or_config_list(&cli.repo_exclude, "repo_exclude"),
+    )?;
+
+    let jobs = match cli.jobs {
+        Some(jobs) => jobs,
+        None => match env::var("jobs") {
+            Ok(jobs) => jobs
+                .parse()
+                .map_err(|_| anyhow::anyhow!("jobs must be a number of threads"))?,
+            Err(_) => num_cpus::get(),
+        },
+    };
+    if jobs == 0 {
+        anyhow::bail!("--jobs must be at least 1");
+    }
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build_global()?;
+    let verbose = cli.verbose || cli.profile;
+
+    if let Some(command) = cli.command.as_ref().filter(|c| !c.counts_commits()) {
+        reject_flags(
+            &[
+                ("--grep", cli.grep.is_some()),
+                ("--exclude-grep", cli.exclude_grep.is_some()),
+                ("--path", !cli.paths.is_empty()),
+                ("--no-co-authors", cli.no_co_authors),
+                ("--source", cli.source != stats::ActivitySource::Commits),
+                ("--lang", cli.lang.is_some()),
+                ("--languages", cli.languages),
+                ("--compare", cli.compare.is_some()),
+                ("--view", !matches!(cli.view, View::Grid)),
+            ],
+            &format!("{}, which doesn't count commits", command.name()),
+        )?;
+    }
+
+    let team = match &cli.command {
+        Some(Command::Team(args)) => Some(args),
+        _ => None,
+    };
+    if team.is_some() {
+        // The leaderboard has no single graph, summary or language table to show them in
+        reject_flags(
+            &[
+                ("--compare", cli.compare.is_some()),
+                ("--languages", cli.languages),
+                ("--view", !matches!(cli.view, View::Grid)),
+            ],
+            "team mode",
+        )?;
+    }
+    if cli.compare.is_some() {
+        // The comparison only shows the two grids and the change between them
+        reject_flags(
+            &[
+                ("--languages", cli.languages),
+                ("--view", !matches!(cli.view, View::Grid)),
+                ("--source", cli.source != stats::ActivitySource::Commits),
+            ],
+            "--compare",
+        )?;
+    }
+    let machine = cli.format != export::Format::Terminal;
+    if machine && (cli.command.is_some() || cli.compare.is_some()) {
+        anyhow::bail!("--format can't be combined with a subcommand or --compare");
+    }
+    if machine && matches!(cli.view, View::Punchcard) {
+        anyhow::bail!("--format writes daily counts, it can't be combined with --view punchcard");
+    }
+    if cli.languages && machine && cli.format != export::Format::Json {
+        anyhow::bail!("--languages can only be exported with --format json");
+    }
+    if !machine && cli.output.is_some() {
+        anyhow::bail!("--output needs --format json, csv, tsv or svg");
+    }
+
+    let today = Local::now().date_naive();
+    let window = compare::Period {
+        start: today - Duration::days(stats::DAYS_IN_LAST_SIX_MONTHS),
+        end: today,
+    };
+    // Both periods come out of a single walk reaching back to the earlier one
+    let periods = cli.compare.as_ref().map(|c| c.periods(window));
+    let days = periods.map_or(stats::DAYS_IN_LAST_SIX_MONTHS, |(before, _)| {
+        (today - before.start).num_days().max(0) + 1
+    });
+
+    chatter!(
+        machine,
         "Scanning {} for commits by {}...",
         folder_path.display().to_string().cyan(),
-        email.cyan()
+        if team.is_some() {
+            "everyone".cyan()
+        } else {
+            email.cyan()
+        }
     );
 
     // Step 1: Scan
     let step_start = Instant::now();
     let cache_arg = if cli.rescan { None } else { Some(&cache_path) };
-    let repos = scanner::scan(folder_path.to_str().unwrap(), cache_arg);
-    if cli.verbose {
-        println!("[Perf] Scan/Cache Load: {:.2?}", step_start.elapsed());
-        println!("[Info] Processing {} repositories", repos.len());
+    let repos = repo_filter.apply(scanner::scan(
+        folder_path.to_str().unwrap(),
+        cache_arg,
+        jobs,
+    ));
+    if verbose {
+        chatter!(
+            machine,
+            "[Perf] Scan/Cache Load: {:.2?}",
+            step_start.elapsed()
+        );
+        chatter!(machine, "[Info] Processing {} repositories", repos.len());
+    }
+
+    if let Some(Command::Orphans) = cli.command {
+        let (found, failures) = orphans::find(repos, &email);
+        ui::print_orphans(&found);
+        ui::print_failures(&failures, cli.verbose);
+        return finish(start_time, cli.strict, failures.len(), false);
+    }
+    if let Some(Command::Unpushed) = cli.command {
+        let (found, failures) = unpushed::find(repos, &email);
+        ui::print_unpushed(&found);
+        ui::print_failures(&failures, cli.verbose);
+        return finish(start_time, cli.strict, failures.len(), false);
+    }
+    if let Some(Command::Stale(args)) = &cli.command {
+        let cutoff = chrono::Utc::now()
+            .checked_sub_months(chrono::Months::new(args.months))
+            .ok_or_else(|| anyhow::anyhow!("--months is too large"))?;
+        let (found, failures) = stale::find(repos, cutoff, args.mine.then_some(email.as_str()));
+        ui::print_stale(&found, args.months);
+        ui::print_failures(&failures, cli.verbose);
+        return finish(start_time, cli.strict, failures.len(), false);
+    }
+    if let Some(Command::Status) = cli.command {
+        let total = repos.len();
+        let (dirty, failures) = status::check(repos);
+        ui::print_status(&dirty, total);
+        ui::print_failures(&failures, cli.verbose);
+        return finish(start_time, cli.strict, failures.len(), false);
     }
 
     // Step 2: Stats
     let step_start = Instant::now();
-    let commit_counts = stats::process_repositories(repos, &email);
-    if cli.verbose {
-        println!("[Perf] Stats Processing: {:.2?}", step_start.elapsed());
+    let query = stats::Query {
+        languages: cli.languages,
+        lang,
+        grep: cli.grep.clone(),
+        exclude_grep: cli.exclude_grep.clone(),
+        paths,
+        days,
+        source: cli.source,
+        all_authors: team.is_some(),
+        co_authors: !cli.no_co_authors,
+        jj_revset: env::var("jj_revset").ok(),
+        jj_cli: jj_cli_options()?,
+        ..stats::Query::new(&email)
+    };
+    let mut stats_cache = if cli.rescan {
+        cache::StatsCache::default()
+    } else {
+        cache::StatsCache::load(&stats_cache_path)
+    };
+    let stats = stats::process_repositories(repos, &query, &mut stats_cache);
+    stats_cache.save(&stats_cache_path);
+    if verbose {
+        chatter!(
+            machine,
+            "[Perf] Stats Processing: {:.2?}",
+            step_start.elapsed()
+        );
+        for clone in &stats.incomplete {
+            let kind = match (clone.shallow, clone.partial) {
+                (true, true) => "shallow and partial",
+                (true, false) => "shallow",
+                _ => "partial",
+            };
+            chatter!(
+                machine,
+                "[Info] {} is a {} clone, its history may be incomplete",
+                clone.path.display(),
+                kind
+            );
+            if clone.truncated {
+                chatter!(
+                    machine,
+                    "[Info] {} is missing commits within the window, they weren't counted",
+                    clone.path.display()
+                );
+            }
+        }
     }
 
     // Step 3: UI
     let step_start = Instant::now();
-    ui::print_stats(&commit_counts);
-    if cli.verbose {
-        println!("[Perf] UI Rendering: {:.2?}", step_start.elapsed());
+    if let Some(args) = team {
+        let identities = team::Identities::parse(&env::var("identities").unwrap_or_default())?;
+        let mut members = team::leaderboard(&stats.by_author, &identities, window.start, today);
+        if let Some(top) = args.top {
+            members.truncate(top);
+        }
+        ui::print_leaderboard(&members, args.heatmaps);
+    } else if let Some((before, after)) = periods {
+        ui::print_comparison(
+            &stats.commits,
+            &compare::PeriodStats::new(&stats.commits, before),
+            &compare::PeriodStats::new(&stats.commits, after),
+        );
+    } else if machine {
+        let summary = summary::summarize(&stats.commits, window.start, today);
+        let export = export::Export {
+            email: &email,
+            commits: &stats.commits,
+            summary: &summary,
+            co_authored: stats.co_authored,
+            start: window.start,
+            end: today,
+            languages: cli.languages.then_some(&stats.languages),
+            events: (cli.source != stats::ActivitySource::Commits).then_some(&stats.events),
+        };
+        let mut out: Box<dyn Write> = match &cli.output {
+            Some(path) => Box::new(BufWriter::new(fs::File::create(path)?)),
+            None => Box::new(std::io::stdout().lock()),
+        };
+        export::write(&mut out, cli.format, &export)?;
+        out.flush()?;
+    } else {
+        match cli.view {
+            View::Grid => ui::print_stats(&stats.commits),
+            View::Punchcard => ui::print_punchcard(&stats.punchcard),
+        }
+        let summary = summary::summarize(&stats.commits, window.start, today);
+        ui::print_summary(&summary, stats.co_authored);
+        if cli.source != stats::ActivitySource::Commits {
+            ui::print_events(&stats.events);
+        }
+        if cli.languages {
+            ui::print_languages(&stats.languages);
+        }
     }
+    if verbose {
+        chatter!(machine, "[Perf] UI Rendering: {:.2?}", step_start.elapsed());
+    }
+    if cli.profile {
+        ui::print_profile(&stats.profiles, PROFILE_TOP);
+    }
+
+    if cli.show_skipped {
+        ui::print_skipped(&stats.skipped);
+    }
+    ui::print_failures(&stats.failures, cli.verbose);
+
+    finish(start_time, cli.strict, stats.failures.len(), machine)
+}
 
+fn finish(start_time: Instant, strict: bool, failures: usize, quiet: bool) -> anyhow::Result<()> {
     let duration = start_time.elapsed();
-    println!("\nDone in {:.2?}", duration);
+    chatter!(quiet, "\nDone in {:.2?}", duration);
+
+    if strict && failures > 0 {
+        anyhow::bail!("{} repositories failed to process", failures);
+    }
 
     Ok(())
 }
 
+/// Fail naming every flag that is `set` but would be ignored alongside `other`.
+fn reject_flags(flags: &[(&str, bool)], other: &str) -> anyhow::Result<()> {
+    let set: Vec<&str> = flags
+        .iter()
+        .filter_map(|&(flag, set)| set.then_some(flag))
+        .collect();
+    if !set.is_empty() {
+        anyhow::bail!("{} can't be combined with {}", set.join(", "), other);
+    }
+    Ok(())
+}
+
+/// Comma-separated values of a config key.
+fn config_list(key: &str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn or_config_list(values: &[String], key: &str) -> Vec<String> {
+    if values.is_empty() {
+        config_list(key)
+    } else {
+        values.to_vec()
+    }
+}
+
+fn jj_cli_options() -> anyhow::Result<jj::CliOptions> {
+    let mut options = jj::CliOptions::default();
+    if let Ok(path) = env::var("jj_path") {
+        options.executable = PathBuf::from(path);
+    }
+    if let Ok(timeout) = env::var("jj_timeout") {
+        let seconds: u64 = timeout
+            .parse()
+            .map_err(|_| anyhow::anyhow!("jj_timeout must be a number of seconds"))?;
+        options.timeout = std::time::Duration::from_secs(seconds);
+    }
+    Ok(options)
+}
+
 fn get_git_config_email() -> Option<String> {
     gix::config::File::from_globals()
         .ok()
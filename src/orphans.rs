@@ -0,0 +1,243 @@
+use crate::stats::{self, RepoFailure};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, FixedOffset};
+use gix::ObjectId;
+use gix::bstr::ByteSlice;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Where an unreachable commit was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    Stash,
+    Reflog,
+    /// A loose commit object nothing refers to anymore.
+    Dangling,
+}
+
+impl Source {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Source::Stash => "stash",
+            Source::Reflog => "reflog",
+            Source::Dangling => "dangling",
+        }
+    }
+}
+
+/// A commit by the user that no branch or tag reaches.
+#[derive(Debug, Clone)]
+pub struct Orphan {
+    pub id: ObjectId,
+    pub time: DateTime<FixedOffset>,
+    pub subject: String,
+    pub source: Source,
+}
+
+#[derive(Debug)]
+pub struct RepoOrphans {
+    pub path: PathBuf,
+    /// Newest first.
+    pub orphans: Vec<Orphan>,
+}
+
+/// Find lost commits by `email` in every Git repository. jj keeps every
+/// change in its operation log, so jj repositories are left alone, colocated
+/// ones included: their unbookmarked changes aren't on any Git branch.
+pub fn find(repos: Vec<PathBuf>, email: &str) -> (Vec<RepoOrphans>, Vec<RepoFailure>) {
+    stats::check_repos(repos, |path| {
+        if !path.join(".git").exists() || path.join(".jj").exists() {
+            return Ok(None);
+        }
+        let orphans = find_in_repo(path, email)?;
+        Ok((!orphans.is_empty()).then(|| RepoOrphans {
+            path: path.to_path_buf(),
+            orphans,
+        }))
+    })
+}
+
+fn find_in_repo(path: &Path, email: &str) -> Result<Vec<Orphan>> {
+    let repo = gix::open(path)?;
+    let references = repo.references()?;
+
+    // Everything reachable from these is safe, including work on a detached HEAD
+    let mut kept: Vec<ObjectId> = repo
+        .head_id()
+        .ok()
+        .map(|id| id.detach())
+        .into_iter()
+        .collect();
+    for reference in references
+        .local_branches()?
+        .chain(references.remote_branches()?)
+        .chain(references.tags()?)
+    {
+        let mut reference = reference.map_err(|e| anyhow!(e))?;
+        if let Ok(commit) = reference.peel_to_commit() {
+            kept.push(commit.id);
+        }
+    }
+
+    let mut stash = Vec::new();
+    let mut reflog = Vec::new();
+    if let Ok(head) = repo.head() {
+        collect_reflog(head.log_iter(), &mut reflog)?;
+    }
+    for reference in references.all()? {
+        let reference = reference.map_err(|e| anyhow!(e))?;
+        let target = if reference.name().as_bstr() == "refs/stash" {
+            &mut stash
+        } else {
+            &mut reflog
+        };
+        collect_reflog(reference.log_iter(), target)?;
+    }
+    let dangling = loose_commits(&repo)?;
+
+    // The index and untracked-files parents of a stash are bookkeeping, not work
+    let mut seen = HashSet::new();
+    for id in &stash {
+        if let Ok(commit) = repo.find_commit(*id) {
+            seen.extend(commit.parent_ids().skip(1).map(|id| id.detach()));
+        }
+    }
+    let mut orphans = Vec::new();
+    for (source, tips) in [
+        (Source::Stash, stash),
+        (Source::Reflog, reflog),
+        (Source::Dangling, dangling),
+    ] {
+        let walk = repo
+            .rev_walk(tips)
+            .with_hidden(kept.iter().copied())
+            .first_parent_only()
+            .all()?;
+        for info in walk {
+            let info = info?;
+            if !seen.insert(info.id) {
+                continue;
+            }
+            let commit = info.object()?;
+            let author = commit.author()?;
+            if !author.email.eq_ignore_ascii_case(email.as_bytes()) {
+                continue;
+            }
+
+            orphans.push(Orphan {
+                id: info.id,
+                time: stats::to_datetime(author.time()?)?,
+                subject: commit.message()?.summary().to_str_lossy().into_owned(),
+                source,
+            });
+        }
+    }
+
+    orphans.sort_by_key(|orphan| std::cmp::Reverse(orphan.time));
+    Ok(orphans)
+}
+
+/// Every commit a reflog entry moved from or to.
+fn collect_reflog(
+    mut log: gix::refs::file::log::iter::Platform<'_, '_>,
+    ids: &mut Vec<ObjectId>,
+) -> Result<()> {
+    let Some(lines) = log.all()? else {
+        return Ok(());
+    };
+    for line in lines {
+        let line = line?;
+        for id in [line.previous_oid(), line.new_oid()] {
+            if !id.is_null() {
+                ids.push(id);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Loose commit objects, where anything dropped since the last `git gc` ends up.
+fn loose_commits(repo: &gix::Repository) -> Result<Vec<ObjectId>> {
+    let store = gix::odb::loose::Store::at(repo.common_dir().join("objects"), repo.object_hash());
+    let mut commits = Vec::new();
+    for id in store.iter() {
+        let id = id?;
+        if repo
+            .find_header(id)
+            .is_ok_and(|header| header.kind() == gix::object::Kind::Commit)
+        {
+            commits.push(id);
+        }
+    }
+    Ok(commits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{git, init_repo};
+    use tempfile::tempdir;
+
+    fn commit(repo_path: &Path, message: &str) {
+        std::fs::write(repo_path.join("file"), message).unwrap();
+        git(repo_path, &["add", "file"]);
+        git(repo_path, &["commit", "-m", message]);
+    }
+
+    #[test]
+    fn test_find_lost_commits() {
+        let dir = tempdir().unwrap();
+        let repo_path = dir.path().join("lost");
+        let email = "test@example.com";
+        init_repo(&repo_path, email);
+
+        commit(&repo_path, "Kept");
+        commit(&repo_path, "Lost after reset");
+        git(&repo_path, &["reset", "--hard", "HEAD~1"]);
+        std::fs::write(repo_path.join("file"), "work in progress").unwrap();
+        git(&repo_path, &["stash"]);
+        // Someone else's lost commit isn't ours to report
+        git(&repo_path, &["config", "user.email", "other@example.com"]);
+        commit(&repo_path, "Not mine");
+        git(&repo_path, &["reset", "--hard", "HEAD~1"]);
+
+        let (found, failures) = find(vec![repo_path.clone()], email);
+        assert!(failures.is_empty());
+        assert_eq!(found.len(), 1);
+        let orphans: Vec<(&str, Source)> = found[0]
+            .orphans
+            .iter()
+            .map(|o| (o.subject.as_str(), o.source))
+            .collect();
+        assert_eq!(orphans.len(), 2);
+        assert!(orphans.contains(&("Lost after reset", Source::Reflog)));
+        assert!(
+            orphans
+                .iter()
+                .any(|(subject, source)| subject.starts_with("WIP on") && *source == Source::Stash)
+        );
+    }
+
+    #[test]
+    fn test_detached_head_is_not_lost() {
+        let dir = tempdir().unwrap();
+        let repo_path = dir.path().join("detached");
+        let email = "test@example.com";
+        init_repo(&repo_path, email);
+
+        commit(&repo_path, "On a branch");
+        git(&repo_path, &["checkout", "--detach"]);
+        commit(&repo_path, "Detached work");
+
+        let (found, failures) = find(vec![repo_path.clone()], email);
+        assert!(failures.is_empty());
+        assert!(found.is_empty());
+
+        // Colocated jj repositories aren't checked at all
+        git(&repo_path, &["checkout", "-"]);
+        std::fs::create_dir(repo_path.join(".jj")).unwrap();
+        let (found, failures) = find(vec![repo_path], email);
+        assert!(failures.is_empty());
+        assert!(found.is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+use crate::stats::{self, Contribution, Query};
+use anyhow::Result;
+use gix::bstr::ByteSlice;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What a HEAD reflog entry records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Event {
+    Commit,
+    Amend,
+    Rebase,
+    CherryPick,
+    Merge,
+    Reset,
+    Checkout,
+    Other,
+}
+
+impl Event {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Event::Commit => "commits",
+            Event::Amend => "amends",
+            Event::Rebase => "rebases",
+            Event::CherryPick => "cherry-picks",
+            Event::Merge => "merges",
+            Event::Reset => "resets",
+            Event::Checkout => "checkouts",
+            Event::Other => "other",
+        }
+    }
+
+    /// Whether the entry points at a commit it just created.
+    fn creates_commit(&self) -> bool {
+        matches!(self, Event::Commit | Event::Amend | Event::CherryPick)
+    }
+}
+
+/// Classify a reflog message like `commit (amend): Fix typo`, returning the event
+/// and the rest of the message. Intermediate rebase steps return `None`, so a
+/// rebase counts once, when it finishes.
+pub fn classify(message: &str) -> Option<(Event, &str)> {
+    let (action, rest) = message.split_once(": ").unwrap_or((message, ""));
+    let event = match action {
+        "commit (amend)" => Event::Amend,
+        _ if action == "commit" || action.starts_with("commit (") => Event::Commit,
+        // `git pull --rebase` logs its steps like a rebase, under its own name
+        _ if action.starts_with("rebase") || action.starts_with("pull --rebase") => {
+            if !action.ends_with("(finish)") {
+                return None;
+            }
+            Event::Rebase
+        }
+        "cherry-pick" => Event::CherryPick,
+        "checkout" => Event::Checkout,
+        "reset" => Event::Reset,
+        _ if action.starts_with("merge") || action.starts_with("pull") => Event::Merge,
+        _ => Event::Other,
+    };
+    Some((event, rest))
+}
+
+/// Identifies the newest HEAD reflog entry, so new entries invalidate the stats cache.
+pub fn tip(head: &gix::Head<'_>) -> Result<String> {
+    let mut log = head.log_iter();
+    let newest = match log.rev()? {
+        Some(mut lines) => lines
+            .next()
+            .transpose()?
+            .map(|line| format!("reflog:{}:{}", line.new_oid, line.signature.time.seconds)),
+        None => None,
+    };
+    Ok(newest.unwrap_or_else(|| "reflog:".to_string()))
+}
+
+/// Count the HEAD reflog entries made within the window by the queried identity.
+///
+/// With `skip_reachable`, commits still reachable from `head_id` are left to the
+/// history walk so they aren't counted twice.
+pub fn contributions(
+    repo: &gix::Repository,
+    head: &gix::Head<'_>,
+    head_id: gix::ObjectId,
+    query: &Query,
+    skip_reachable: bool,
+) -> Result<Vec<Contribution>> {
+    let cutoff_date = query.cutoff();
+    let mut contributions = Vec::new();
+    let mut log = head.log_iter();
+    let Some(lines) = log.rev()? else {
+        return Ok(contributions);
+    };
+
+    // Newest first, so stop at the first entry older than the window
+    for line in lines {
+        let line = line?;
+        let datetime = stats::to_datetime(line.signature.time)?;
+        if datetime < cutoff_date {
+            break;
+        }
+
+        let message = line.message.to_str_lossy();
+        let Some((event, subject)) = classify(&message) else {
+            continue;
+        };
+        if skip_reachable && event.creates_commit() {
+            let new_oid = line.new_oid;
+            if repo
+                .merge_base(new_oid, head_id)
+                .is_ok_and(|base| base == new_oid)
+            {
+                continue;
+            }
+        }
+
+        contributions.extend(
+            query
+                .credits(&line.signature.email.to_str_lossy(), subject)
+                .into_iter()
+                .map(|credit| Contribution {
+                    event: Some(event),
+                    ..credit.contribution(datetime, HashMap::new())
+                }),
+        );
+    }
+
+    Ok(contributions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify("commit (initial): Start"),
+            Some((Event::Commit, "Start"))
+        );
+        assert_eq!(
+            classify("commit (amend): Fix typo"),
+            Some((Event::Amend, "Fix typo"))
+        );
+        assert_eq!(
+            classify("rebase (finish): returning to refs/heads/main"),
+            Some((Event::Rebase, "returning to refs/heads/main"))
+        );
+        assert_eq!(
+            classify("rebase -i (finish): returning to refs/heads/main").map(|(e, _)| e),
+            Some(Event::Rebase)
+        );
+        assert_eq!(classify("rebase (pick): Add parser"), None);
+        assert_eq!(classify("pull --rebase (start): checkout abc123"), None);
+        assert_eq!(classify("pull --rebase (pick): Add parser"), None);
+        assert_eq!(classify("pull --rebase (continue): Add parser"), None);
+        assert_eq!(
+            classify("pull --rebase (finish): returning to refs/heads/main").map(|(e, _)| e),
+            Some(Event::Rebase)
+        );
+        assert_eq!(
+            classify("pull: Fast-forward").map(|(e, _)| e),
+            Some(Event::Merge)
+        );
+        assert_eq!(
+            classify("checkout: moving from main to topic").map(|(e, _)| e),
+            Some(Event::Checkout)
+        );
+        assert_eq!(
+            classify("merge topic: Fast-forward").map(|(e, _)| e),
+            Some(Event::Merge)
+        );
+        assert_eq!(
+            classify("clone: from /tmp/x").map(|(e, _)| e),
+            Some(Event::Other)
+        );
+    }
+}
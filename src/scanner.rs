@@ -1,11 +1,12 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use ignore::WalkBuilder;
 use std::collections::HashSet;
 use std::fs::File;
 use std::io::BufReader;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
-pub fn scan(root: &str, cache_file: Option<&PathBuf>) -> Vec<PathBuf> {
+pub fn scan(root: &str, cache_file: Option<&PathBuf>, threads: usize) -> Vec<PathBuf> {
     if let Some(path) = cache_file
         && path.exists()
         && let Ok(file) = File::open(path)
@@ -20,7 +21,7 @@ pub fn scan(root: &str, cache_file: Option<&PathBuf>) -> Vec<PathBuf> {
     let repos_clone = repos.clone();
 
     WalkBuilder::new(root)
-        .threads(num_cpus::get())
+        .threads(threads)
         .follow_links(true)
         .standard_filters(false) // Don't respect gitignore for the repo search itself, we want to find repos!
         .hidden(false) // We need to see .git
@@ -54,6 +55,49 @@ pub fn scan(root: &str, cache_file: Option<&PathBuf>) -> Vec<PathBuf> {
     result
 }
 
+/// Narrows discovered repositories down by globs on their path or directory name.
+#[derive(Debug, Default)]
+pub struct RepoFilter {
+    /// When set, only repositories matching one of these are kept.
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl RepoFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> anyhow::Result<Self> {
+        Ok(RepoFilter {
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(glob_set(include)?)
+            },
+            exclude: glob_set(exclude)?,
+        })
+    }
+
+    pub fn matches(&self, repo: &Path) -> bool {
+        let is_match = |set: &GlobSet| {
+            set.is_match(repo) || repo.file_name().is_some_and(|name| set.is_match(name))
+        };
+        self.include.as_ref().is_none_or(is_match) && !is_match(&self.exclude)
+    }
+
+    pub fn apply(&self, repos: Vec<PathBuf>) -> Vec<PathBuf> {
+        repos
+            .into_iter()
+            .filter(|repo| self.matches(repo))
+            .collect()
+    }
+}
+
+fn glob_set(globs: &[String]) -> anyhow::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob)?);
+    }
+    Ok(builder.build()?)
+}
+
 fn is_ignored(name: &str) -> bool {
     name == "node_modules" || name == "vendor"
 }
@@ -75,7 +119,7 @@ mod tests {
         fs::create_dir(&repo_dir).unwrap();
         fs::create_dir(repo_dir.join(".git")).unwrap();
 
-        let found = scan(dir.path().to_str().unwrap(), None);
+        let found = scan(dir.path().to_str().unwrap(), None, 2);
         assert_eq!(found.len(), 1);
         assert_eq!(found[0], repo_dir);
     }
@@ -87,7 +131,7 @@ mod tests {
         fs::create_dir(&repo_dir).unwrap();
         fs::create_dir(repo_dir.join(".jj")).unwrap();
 
-        let found = scan(dir.path().to_str().unwrap(), None);
+        let found = scan(dir.path().to_str().unwrap(), None, 2);
         assert_eq!(found.len(), 1);
         assert_eq!(found[0], repo_dir);
     }
@@ -100,7 +144,7 @@ mod tests {
         fs::create_dir(repo_dir.join(".git")).unwrap();
         fs::create_dir(repo_dir.join(".jj")).unwrap();
 
-        let found = scan(dir.path().to_str().unwrap(), None);
+        let found = scan(dir.path().to_str().unwrap(), None, 2);
         assert_eq!(found.len(), 1);
         assert_eq!(found[0], repo_dir);
     }
@@ -114,7 +158,7 @@ mod tests {
         let sub_git = node_dir.join("dep").join(".git");
         fs::create_dir_all(&sub_git).unwrap();
 
-        let found = scan(dir.path().to_str().unwrap(), None);
+        let found = scan(dir.path().to_str().unwrap(), None, 2);
         assert_eq!(found.len(), 0);
     }
 
@@ -126,7 +170,36 @@ mod tests {
         let sub_git = vendor_dir.join("dep").join(".git");
         fs::create_dir_all(&sub_git).unwrap();
 
-        let found = scan(dir.path().to_str().unwrap(), None);
+        let found = scan(dir.path().to_str().unwrap(), None, 2);
         assert_eq!(found.len(), 0);
     }
+
+    #[test]
+    fn test_repo_filter() {
+        let repos: Vec<PathBuf> = [
+            "/home/me/Repos/client-acme",
+            "/home/me/Repos/client-globex",
+            "/home/me/Repos/dotfiles",
+            "/home/me/scratch/client-test",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+
+        let filter =
+            RepoFilter::new(&["client-*".to_string()], &["**/scratch/**".to_string()]).unwrap();
+        assert_eq!(
+            filter.apply(repos.clone()),
+            vec![
+                PathBuf::from("/home/me/Repos/client-acme"),
+                PathBuf::from("/home/me/Repos/client-globex"),
+            ]
+        );
+
+        let filter = RepoFilter::new(&[], &["dotfiles".to_string()]).unwrap();
+        assert_eq!(filter.apply(repos.clone()).len(), 3);
+
+        assert_eq!(RepoFilter::default().apply(repos).len(), 4);
+        assert!(RepoFilter::new(&["[".to_string()], &[]).is_err());
+    }
 }
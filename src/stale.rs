@@ -0,0 +1,176 @@
+use crate::jj;
+use crate::stats::{self, RepoFailure};
+use crate::unpushed;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, FixedOffset, Utc};
+use gix::revision::walk::Sorting;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A repository nobody, or the queried identity, has committed to in a while.
+#[derive(Debug, Clone)]
+pub struct StaleRepo {
+    pub path: PathBuf,
+    /// `None` if there are no commits at all, or none by the queried identity.
+    pub last_commit: Option<DateTime<FixedOffset>>,
+    /// Bytes in the checkout, without the `.git` or `.jj` directory.
+    pub checkout_size: u64,
+    /// Bytes in the `.git` or `.jj` directory.
+    pub vcs_size: u64,
+    /// Whether every branch is on a remote. `None` for jj-only repositories,
+    /// which don't have remote-tracking branches to compare with.
+    pub pushed: Option<bool>,
+}
+
+/// Find repositories whose newest commit, or newest commit by `email` if given,
+/// is older than `cutoff`. Oldest first.
+pub fn find(
+    repos: Vec<PathBuf>,
+    cutoff: DateTime<Utc>,
+    email: Option<&str>,
+) -> (Vec<StaleRepo>, Vec<RepoFailure>) {
+    let (mut found, failures) = stats::check_repos(repos, |path| check(path, cutoff, email));
+    // Stable, so repositories with the same last commit stay in path order
+    found.sort_by_key(|repo| repo.last_commit);
+    (found, failures)
+}
+
+fn check(path: &Path, cutoff: DateTime<Utc>, email: Option<&str>) -> Result<Option<StaleRepo>> {
+    let (last_commit, repo, vcs_dir) = if path.join(".git").exists() {
+        let repo = gix::open(path)?;
+        (last_git_commit(&repo, email)?, Some(repo), ".git")
+    } else if path.join(".jj").exists() {
+        (jj::last_commit(path, email, cutoff)?, None, ".jj")
+    } else {
+        return Ok(None);
+    };
+    if last_commit.is_some_and(|time| time >= cutoff) {
+        return Ok(None);
+    }
+    // Only worth walking the branches of repositories we're going to report
+    let pushed = repo.map(|repo| unpushed::all_pushed(&repo)).transpose()?;
+
+    let vcs_size = disk_usage(&path.join(vcs_dir));
+    Ok(Some(StaleRepo {
+        path: path.to_path_buf(),
+        last_commit,
+        checkout_size: disk_usage(path).saturating_sub(vcs_size),
+        vcs_size,
+        pushed,
+    }))
+}
+
+/// The author date of the newest commit on any local branch or HEAD.
+fn last_git_commit(
+    repo: &gix::Repository,
+    email: Option<&str>,
+) -> Result<Option<DateTime<FixedOffset>>> {
+    let mut tips = Vec::new();
+    if let Ok(id) = repo.head_id() {
+        tips.push(id.detach());
+    }
+    for reference in repo.references()?.local_branches()? {
+        let mut reference = reference.map_err(|e| anyhow!(e))?;
+        if let Ok(commit) = reference.peel_to_commit() {
+            tips.push(commit.id);
+        }
+    }
+
+    // Newest first by commit date. A commit can't be authored after it was
+    // committed, so once commit dates drop below the newest author date found,
+    // nothing further back can beat it.
+    let walk = repo
+        .rev_walk(tips)
+        .sorting(Sorting::ByCommitTime(Default::default()))
+        .all()?;
+    let mut newest: Option<DateTime<FixedOffset>> = None;
+    for info in walk {
+        let info = info?;
+        if let (Some(newest), Some(committed)) = (newest, info.commit_time)
+            && committed < newest.timestamp()
+        {
+            break;
+        }
+        let commit = info.object()?;
+        let author = commit.author()?;
+        if email.is_some_and(|email| !author.email.eq_ignore_ascii_case(email.as_bytes())) {
+            continue;
+        }
+        let time = stats::to_datetime(author.time()?)?;
+        if newest.is_none_or(|newest| time > newest) {
+            newest = Some(time);
+        }
+    }
+    Ok(newest)
+}
+
+/// Total size of the files under `dir`, without following symlinks.
+fn disk_usage(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(ft) if ft.is_dir() => disk_usage(&entry.path()),
+            Ok(_) => entry.metadata().map_or(0, |m| m.len()),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{git, git_at, init_repo};
+    use chrono::Duration;
+    use tempfile::tempdir;
+
+    fn commit(repo_path: &Path, email: &str, date: DateTime<Utc>) {
+        std::fs::write(repo_path.join("file"), date.to_rfc3339()).unwrap();
+        let date = date.to_rfc3339();
+        git(repo_path, &["add", "file"]);
+        git_at(
+            repo_path,
+            &[
+                "-c",
+                &format!("user.email={}", email),
+                "commit",
+                "-m",
+                "Commit",
+            ],
+            &date,
+        );
+    }
+
+    #[test]
+    fn test_find_stale_repos() {
+        let dir = tempdir().unwrap();
+        let email = "test@example.com";
+        let now = Utc::now();
+        let cutoff = now - Duration::days(180);
+
+        let active = dir.path().join("active");
+        init_repo(&active, email);
+        commit(&active, email, now - Duration::days(400));
+        commit(&active, "other@example.com", now - Duration::days(10));
+
+        let abandoned = dir.path().join("abandoned");
+        init_repo(&abandoned, email);
+        commit(&abandoned, email, now - Duration::days(300));
+
+        let repos = vec![active.clone(), abandoned.clone()];
+        let (found, failures) = find(repos.clone(), cutoff, None);
+        assert!(failures.is_empty());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, abandoned);
+        assert_eq!(found[0].pushed, Some(false));
+        assert!(found[0].checkout_size > 0);
+        assert!(found[0].vcs_size > found[0].checkout_size);
+
+        // Someone else keeps `active` going, but we haven't touched it in a while
+        let (found, _) = find(repos, cutoff, Some("Test@Example.com"));
+        let found: Vec<&Path> = found.iter().map(|repo| repo.path.as_path()).collect();
+        assert_eq!(found, vec![active.as_path(), abandoned.as_path()]);
+    }
+}
@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The most recent on-disk change that hints at new commits in a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Activity {
+    pub time: SystemTime,
+    /// The file the time was taken from, relative to the repository root.
+    pub source: PathBuf,
+}
+
+/// Find the newest signal that changes whenever commits are made.
+///
+/// `.git/HEAD` alone only changes on checkout, so for Git we look at loose refs,
+/// `packed-refs` and the reflogs as well. For jj, every operation rewrites the op heads.
+/// Returns `None` if no signal could be read, in which case the repo shouldn't be skipped.
+pub fn last_activity(repo: &Path) -> Option<Activity> {
+    let mut signals = Vec::new();
+
+    let git_dir = repo.join(".git");
+    if git_dir.is_dir() {
+        signals.push(git_dir.join("HEAD"));
+        signals.push(git_dir.join("packed-refs"));
+        collect_files(&git_dir.join("refs").join("heads"), &mut signals);
+        collect_files(&git_dir.join("logs"), &mut signals);
+    }
+
+    let op_heads = repo.join(".jj").join("repo").join("op_heads").join("heads");
+    if op_heads.is_dir() {
+        signals.push(op_heads.clone());
+        collect_files(&op_heads, &mut signals);
+    }
+
+    signals
+        .into_iter()
+        .filter_map(|path| {
+            let time = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            let source = path.strip_prefix(repo).unwrap_or(&path).to_path_buf();
+            Some(Activity { time, source })
+        })
+        .max_by_key(|activity| activity.time)
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        match entry.file_type() {
+            Ok(ft) if ft.is_dir() => collect_files(&entry.path(), out),
+            Ok(_) => out.push(entry.path()),
+            Err(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn touch(path: &Path, age_days: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let file = File::create(path).unwrap();
+        let time = SystemTime::now() - Duration::from_secs(age_days * 24 * 60 * 60);
+        file.set_modified(time).unwrap();
+    }
+
+    #[test]
+    fn test_git_commit_on_old_checkout() {
+        let dir = tempdir().unwrap();
+        let git_dir = dir.path().join(".git");
+        touch(&git_dir.join("HEAD"), 400);
+        touch(&git_dir.join("refs").join("heads").join("main"), 1);
+
+        let activity = last_activity(dir.path()).unwrap();
+        assert_eq!(activity.source, Path::new(".git/refs/heads/main"));
+    }
+
+    #[test]
+    fn test_git_reflog_counts() {
+        let dir = tempdir().unwrap();
+        let git_dir = dir.path().join(".git");
+        touch(&git_dir.join("HEAD"), 400);
+        touch(&git_dir.join("packed-refs"), 300);
+        touch(&git_dir.join("logs").join("HEAD"), 2);
+
+        let activity = last_activity(dir.path()).unwrap();
+        assert_eq!(activity.source, Path::new(".git/logs/HEAD"));
+    }
+
+    #[test]
+    fn test_jj_op_heads() {
+        let dir = tempdir().unwrap();
+        let heads = dir
+            .path()
+            .join(".jj")
+            .join("repo")
+            .join("op_heads")
+            .join("heads");
+        touch(&heads.join("abc123"), 400);
+        File::open(&heads)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(400 * 24 * 60 * 60))
+            .unwrap();
+
+        let activity = last_activity(dir.path()).unwrap();
+        let age = SystemTime::now().duration_since(activity.time).unwrap();
+        assert!(age > Duration::from_secs(399 * 24 * 60 * 60));
+    }
+
+    #[test]
+    fn test_unknown_layout() {
+        let dir = tempdir().unwrap();
+        assert_eq!(last_activity(dir.path()), None);
+    }
+}
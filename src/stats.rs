@@ -1,131 +1,830 @@
+use crate::cache::{RepoEntry, StatsCache};
+use crate::jj;
+use crate::languages;
+use crate::reflog;
+use crate::staleness::{self, Activity};
 use anyhow::{Result, anyhow};
-use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, Timelike, Utc};
+use gix::bstr::ByteSlice;
+use gix::revision::walk::Sorting;
+use gix::traverse::commit::simple::CommitTimeOrder;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-// use gix::bstr::ByteSlice;
-
-// JJ imports - tentative based on common API patterns
-// If these fail, we might need to adjust or fallback to CLI
-// We will comment them out for the first pass and verify dependencies
-// use jj_lib::workspace::Workspace;
-// use jj_lib::settings::UserSettings;
+use std::str::FromStr;
 
 pub type CommitCounts = HashMap<NaiveDate, i32>;
 
-const DAYS_IN_LAST_SIX_MONTHS: i64 = 183;
-
-pub fn process_repositories(repos: Vec<PathBuf>, email: &str) -> CommitCounts {
-    // Convert email to bytes for gix comparison
-    let email_bytes = email.as_bytes().to_vec();
-
-    repos
-        .par_iter()
-        .fold(HashMap::new, |mut acc: CommitCounts, path| {
-            let mut repo_commits = HashMap::new();
-
-            // Detect repo type
-            let git_dir = path.join(".git");
-            let jj_dir = path.join(".jj");
-
-            let six_months_ago = std::time::SystemTime::now()
-                .checked_sub(std::time::Duration::from_secs(
-                    DAYS_IN_LAST_SIX_MONTHS as u64 * 24 * 60 * 60,
-                ))
-                .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-
-            if git_dir.exists() {
-                // Optimization: Check if HEAD has been modified recently
-                if let Ok(metadata) = std::fs::metadata(git_dir.join("HEAD"))
-                    && let Ok(mtime) = metadata.modified()
-                    && mtime < six_months_ago
-                {
-                    return acc; // Skip stale repo
-                }
+/// Commits and changed lines (added + removed) attributed to a single language.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LanguageCount {
+    pub commits: i32,
+    pub lines: u64,
+}
 
-                if let Err(_e) = process_git(path, &email_bytes, &mut repo_commits) {
-                    // Silently ignore errors
-                }
-            } else if jj_dir.exists() {
-                // Optimization for JJ
-                if let Ok(metadata) = std::fs::metadata(&jj_dir)
-                    && let Ok(mtime) = metadata.modified()
-                    && mtime < six_months_ago
-                {
-                    return acc;
-                }
+pub type LanguageCounts = HashMap<String, LanguageCount>;
 
-                if process_jj(path, email, &mut repo_commits).is_err() {
-                    // Silently ignore errors
-                }
-            }
+/// Commits by weekday (Sunday first) and hour, in the author's own timezone.
+pub type Punchcard = [[i32; 24]; 7];
+
+pub const DAYS_IN_LAST_SIX_MONTHS: i64 = 183;
+
+/// Where activity in Git repositories is read from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+pub enum ActivitySource {
+    /// Commits reachable from HEAD
+    #[default]
+    Commits,
+    /// HEAD reflog entries: commits, amends, rebases, checkouts, ...
+    Reflog,
+    /// Reachable commits plus reflog entries for everything else
+    Both,
+}
+
+impl ActivitySource {
+    fn counts_commits(&self) -> bool {
+        *self != ActivitySource::Reflog
+    }
+
+    fn uses_reflog(&self) -> bool {
+        *self != ActivitySource::Commits
+    }
+}
+
+/// What to count while walking the history of each repository.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Query {
+    pub email: String,
+    /// How many days back from now to count commits.
+    pub days: i64,
+    /// Diff every matching commit to attribute it to languages.
+    pub languages: bool,
+    /// Only count commits touching files of this language.
+    pub lang: Option<&'static str>,
+    /// Only count commits whose message matches.
+    #[serde(serialize_with = "serialize_regex")]
+    pub grep: Option<Regex>,
+    /// Don't count commits whose message matches.
+    #[serde(serialize_with = "serialize_regex")]
+    pub exclude_grep: Option<Regex>,
+    /// Only count commits touching a file matching one of these globs.
+    pub paths: Vec<PathFilter>,
+    /// Git activity to count. jj repositories always count commits.
+    pub source: ActivitySource,
+    /// Count every author (team mode), keyed by email, instead of only `email`.
+    pub all_authors: bool,
+    /// Also count commits crediting `email` in a `Co-authored-by:` trailer.
+    pub co_authors: bool,
+    /// Revset selecting the jj commits to count, instead of `jj::DEFAULT_REVSET`.
+    pub jj_revset: Option<String>,
+    /// Doesn't change what's counted, so it's left out of the fingerprint.
+    #[serde(skip)]
+    pub jj_cli: jj::CliOptions,
+}
+
+impl Query {
+    pub fn new(email: &str) -> Self {
+        Query {
+            email: email.to_string(),
+            days: DAYS_IN_LAST_SIX_MONTHS,
+            co_authors: true,
+            ..Default::default()
+        }
+    }
+
+    /// Commits authored before this fall outside the window.
+    pub(crate) fn cutoff(&self) -> DateTime<Utc> {
+        Utc::now() - Duration::days(self.days)
+    }
+
+    fn needs_diff(&self) -> bool {
+        self.languages || self.lang.is_some()
+    }
+
+    /// Who a commit by `author_email` with `message` counts for, if anyone.
+    pub(crate) fn credits(&self, author_email: &str, message: &str) -> Vec<Credit> {
+        if self.grep.as_ref().is_some_and(|re| !re.is_match(message))
+            || self
+                .exclude_grep
+                .as_ref()
+                .is_some_and(|re| re.is_match(message))
+        {
+            return Vec::new();
+        }
+
+        let co_authors = if self.co_authors {
+            co_author_emails(message)
+        } else {
+            Vec::new()
+        };
+
+        if !self.all_authors {
+            let credit = if author_email.eq_ignore_ascii_case(&self.email) {
+                Some(false)
+            } else if co_authors
+                .iter()
+                .any(|email| email.eq_ignore_ascii_case(&self.email))
+            {
+                Some(true)
+            } else {
+                None
+            };
+            return credit
+                .map(|co_authored| Credit {
+                    author: None,
+                    co_authored,
+                })
+                .into_iter()
+                .collect();
+        }
 
-            // Merge local repo stats into the fold accumulator
-            for (date, count) in repo_commits {
-                *acc.entry(date).or_insert(0) += count;
+        // Emails are case-insensitive in practice, so key authors by the lowercase form
+        let author = author_email.to_lowercase();
+        let mut credits = vec![Credit {
+            author: Some(author.clone()),
+            co_authored: false,
+        }];
+        for email in co_authors {
+            let email = email.to_lowercase();
+            if credits
+                .iter()
+                .all(|credit| credit.author.as_ref() != Some(&email))
+            {
+                credits.push(Credit {
+                    author: Some(email),
+                    co_authored: true,
+                });
             }
-            acc
+        }
+        credits
+    }
+
+    /// The `paths` globs that apply to the repository at `repo`.
+    pub(crate) fn path_globs(&self, repo: &Path) -> Vec<&str> {
+        self.paths
+            .iter()
+            .filter(|filter| filter.applies_to(repo))
+            .map(|filter| filter.glob.as_str())
+            .collect()
+    }
+
+    /// Identifies everything that influences which commits match, for the stats cache.
+    fn fingerprint(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+fn serialize_regex<S: Serializer>(regex: &Option<Regex>, serializer: S) -> Result<S::Ok, S::Error> {
+    regex.as_ref().map(Regex::as_str).serialize(serializer)
+}
+
+/// A glob matched against repo-relative file paths, written as `GLOB` to apply
+/// to every repository or `REPO=GLOB` for a single one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PathFilter {
+    /// Directory name or full path of the repository.
+    pub repo: Option<String>,
+    pub glob: String,
+}
+
+impl PathFilter {
+    fn applies_to(&self, repo: &Path) -> bool {
+        self.repo.as_deref().is_none_or(|name| {
+            repo == Path::new(name) || repo.file_name().is_some_and(|n| n == name)
+        })
+    }
+}
+
+impl FromStr for PathFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (repo, glob) = match s.split_once('=') {
+            Some((repo, glob)) => (Some(repo.trim().to_string()), glob.trim()),
+            None => (None, s.trim()),
+        };
+        if glob.is_empty() {
+            return Err(anyhow!("Empty path glob in '{}'", s));
+        }
+        Ok(PathFilter {
+            repo,
+            glob: glob.to_string(),
         })
-        .reduce(HashMap::new, |mut a, b| {
-            for (date, count) in b {
-                *a.entry(date).or_insert(0) += count;
+    }
+}
+
+/// A single matching commit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contribution {
+    pub time: DateTime<FixedOffset>,
+    /// Changed lines per language, empty unless the query needs diffs.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub lines: HashMap<String, u64>,
+    /// Credited through a `Co-authored-by:` trailer rather than as the author.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub co_authored: bool,
+    /// Who the commit is credited to, only recorded when counting every author.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// The reflog entry this was counted from, if it's not a reachable commit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<reflog::Event>,
+}
+
+/// One identity a matching commit counts for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Credit {
+    pub author: Option<String>,
+    pub co_authored: bool,
+}
+
+impl Credit {
+    pub(crate) fn contribution(
+        self,
+        time: DateTime<FixedOffset>,
+        lines: HashMap<String, u64>,
+    ) -> Contribution {
+        Contribution {
+            time,
+            lines,
+            co_authored: self.co_authored,
+            author: self.author,
+            event: None,
+        }
+    }
+}
+
+/// Emails listed in `Co-authored-by:` trailers at the end of `message`.
+fn co_author_emails(message: &str) -> Vec<&str> {
+    // Trailers live in the last paragraph of the message
+    let trailers = message.trim_end().rsplit("\n\n").next().unwrap_or_default();
+    trailers
+        .lines()
+        .filter_map(|line| {
+            let (token, value) = line.split_once(':')?;
+            if !token.trim().eq_ignore_ascii_case("co-authored-by") {
+                return None;
             }
-            a
+            let (_, rest) = value.rsplit_once('<')?;
+            let (address, _) = rest.split_once('>')?;
+            Some(address.trim())
+        })
+        .collect()
+}
+
+/// A repository whose history couldn't be read.
+#[derive(Debug)]
+pub struct RepoFailure {
+    pub path: PathBuf,
+    pub error: anyhow::Error,
+}
+
+/// Run `check` on every repository in parallel, keeping what it reports and
+/// the repositories it failed on, both in path order.
+pub fn check_repos<T: Send>(
+    mut repos: Vec<PathBuf>,
+    check: impl Fn(&Path) -> Result<Option<T>> + Sync,
+) -> (Vec<T>, Vec<RepoFailure>) {
+    repos.sort();
+    let results: Vec<(PathBuf, Result<Option<T>>)> = repos
+        .into_par_iter()
+        .map(|path| {
+            let result = check(&path);
+            (path, result)
+        })
+        .collect();
+
+    let mut found = Vec::new();
+    let mut failures = Vec::new();
+    for (path, result) in results {
+        match result {
+            Ok(report) => found.extend(report),
+            Err(error) => failures.push(RepoFailure { path, error }),
+        }
+    }
+    (found, failures)
+}
+
+/// A repository skipped because nothing in it changed within the window.
+#[derive(Debug)]
+pub struct SkippedRepo {
+    pub path: PathBuf,
+    pub last_activity: Activity,
+}
+
+/// What read a repository's history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Git,
+    JjLib,
+    /// The `jj` CLI, for repositories jj-lib can't load.
+    JjCli,
+}
+
+impl Backend {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Backend::Git => "git",
+            Backend::JjLib => "jj-lib",
+            Backend::JjCli => "jj cli",
+        }
+    }
+}
+
+/// How long a repository took to process, for `--profile`.
+#[derive(Debug, Clone)]
+pub struct RepoProfile {
+    pub path: PathBuf,
+    pub elapsed: std::time::Duration,
+    /// `None` if the repository was answered from the cache or failed.
+    pub backend: Option<Backend>,
+    pub visited: usize,
+    pub failed: bool,
+}
+
+/// A Git clone that doesn't have all of its history locally, so it may be
+/// missing older commits (shallow) or trees and blobs (partial).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncompleteClone {
+    pub path: PathBuf,
+    pub shallow: bool,
+    pub partial: bool,
+    /// Commits in the window were missing locally, so they weren't counted.
+    pub truncated: bool,
+}
+
+impl IncompleteClone {
+    fn detect(path: &Path, repo: &gix::Repository) -> Option<Self> {
+        let config = repo.config_snapshot();
+        let partial = config.string("extensions.partialClone").is_some()
+            || repo.remote_names().iter().any(|name| {
+                config
+                    .boolean(format!("remote.{}.promisor", name).as_str())
+                    .unwrap_or(false)
+            });
+        let shallow = repo.is_shallow();
+        (shallow || partial).then(|| IncompleteClone {
+            path: path.to_path_buf(),
+            shallow,
+            partial,
+            truncated: false,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub commits: CommitCounts,
+    /// How many of the counted commits only credit the user as a co-author.
+    pub co_authored: i32,
+    /// Counted reflog entries per event type.
+    pub events: HashMap<reflog::Event, i32>,
+    /// Daily commits per author email, only filled in when counting every author.
+    pub by_author: HashMap<String, CommitCounts>,
+    pub languages: LanguageCounts,
+    pub punchcard: Punchcard,
+    pub failures: Vec<RepoFailure>,
+    pub skipped: Vec<SkippedRepo>,
+    pub incomplete: Vec<IncompleteClone>,
+    /// Processed and failed repositories, slowest first.
+    pub profiles: Vec<RepoProfile>,
+}
+
+impl Stats {
+    /// Count one matching commit along with the lines it changed per language.
+    fn record(&mut self, contribution: &Contribution) {
+        let time = contribution.time;
+        // The same calendar as `today` in the windows the counts are shown in
+        let date = time.with_timezone(&Local).date_naive();
+        *self.commits.entry(date).or_insert(0) += 1;
+        if contribution.co_authored {
+            self.co_authored += 1;
+        }
+        if let Some(event) = contribution.event {
+            *self.events.entry(event).or_insert(0) += 1;
+        }
+        if let Some(author) = &contribution.author {
+            *self
+                .by_author
+                .entry(author.clone())
+                .or_default()
+                .entry(date)
+                .or_insert(0) += 1;
+        }
+        self.punchcard[time.weekday().num_days_from_sunday() as usize][time.hour() as usize] += 1;
+        for (lang, lines) in &contribution.lines {
+            let entry = self.languages.entry(lang.clone()).or_default();
+            entry.commits += 1;
+            entry.lines += lines;
+        }
+    }
+}
+
+enum Outcome {
+    Processed(RepoEntry, Option<IncompleteClone>),
+    Skipped(SkippedRepo),
+    Failed(RepoFailure),
+}
+
+/// Count matching commits in every repo, answering from `cache` where the repo
+/// hasn't moved since the last run. The cache is updated in place.
+pub fn process_repositories(repos: Vec<PathBuf>, query: &Query, cache: &mut StatsCache) -> Stats {
+    let fingerprint = query.fingerprint();
+    let cutoff_date = query.cutoff();
+
+    let outcomes: Vec<(PathBuf, Outcome, std::time::Duration)> = repos
+        .into_par_iter()
+        .map(|path| {
+            let start = std::time::Instant::now();
+            // Only reuse entries computed for the very same query
+            let cached = cache.get(&path, &fingerprint);
+            let outcome = process_repository(&path, query, cached);
+            (path, outcome, start.elapsed())
         })
+        .collect();
+
+    let mut stats = Stats::default();
+    // Entries for other queries and repositories outside this run stay, so
+    // switching between views and subsets doesn't start from scratch
+    cache.repos.retain(|path, _| path.exists());
+    for (path, outcome, elapsed) in outcomes {
+        let profile = RepoProfile {
+            path: path.clone(),
+            elapsed,
+            backend: None,
+            visited: 0,
+            failed: false,
+        };
+        match outcome {
+            Outcome::Processed(mut entry, incomplete) => {
+                stats.profiles.push(RepoProfile {
+                    backend: entry.backend,
+                    visited: entry.visited,
+                    ..profile
+                });
+                stats.incomplete.extend(incomplete);
+                // The window moves every day, forget what fell out of it
+                entry.contributions.retain(|c| c.time >= cutoff_date);
+                entry.query = fingerprint.clone();
+                for contribution in &entry.contributions {
+                    stats.record(contribution);
+                }
+                cache.insert(path, entry);
+            }
+            Outcome::Skipped(skipped) => stats.skipped.push(skipped),
+            Outcome::Failed(failure) => {
+                stats.profiles.push(RepoProfile {
+                    failed: true,
+                    ..profile
+                });
+                stats.failures.push(failure);
+            }
+        }
+    }
+    stats
+        .profiles
+        .sort_by_key(|profile| std::cmp::Reverse(profile.elapsed));
+    stats
 }
 
-fn process_git(path: &Path, email: &[u8], commits: &mut CommitCounts) -> Result<()> {
+fn process_repository(path: &Path, query: &Query, cached: Option<&RepoEntry>) -> Outcome {
+    // Detect repo type
+    let git_dir = path.join(".git");
+    let jj_dir = path.join(".jj");
+
+    let cutoff: std::time::SystemTime = query.cutoff().into();
+
+    // Optimization: Skip repos where nothing that moves with new commits
+    // (refs, reflogs, jj op heads) has changed within the window
+    if let Some(activity) = staleness::last_activity(path)
+        && activity.time < cutoff
+    {
+        return Outcome::Skipped(SkippedRepo {
+            path: path.to_path_buf(),
+            last_activity: activity,
+        });
+    }
+
+    let result = if git_dir.exists() {
+        process_git(path, query, cached)
+    } else if jj_dir.exists() {
+        jj::process(path, query, cached).map(|entry| (entry, None))
+    } else {
+        Ok((RepoEntry::default(), None))
+    };
+
+    match result {
+        Ok((entry, incomplete)) => Outcome::Processed(entry, incomplete),
+        Err(error) => Outcome::Failed(RepoFailure {
+            path: path.to_path_buf(),
+            error,
+        }),
+    }
+}
+
+fn process_git(
+    path: &Path,
+    query: &Query,
+    cached: Option<&RepoEntry>,
+) -> Result<(RepoEntry, Option<IncompleteClone>)> {
     // Open repo
-    let repo = gix::open(path)?;
+    let mut repo = gix::open(path)?;
+    // Without a commit-graph, sorting by date looks each commit up twice
+    repo.object_cache_size_if_unset(4 * 1024 * 1024);
+    let mut incomplete = IncompleteClone::detect(path, &repo);
+    let partial = incomplete.as_ref().is_some_and(|clone| clone.partial);
 
     // HEAD
     let head = repo.head()?;
-    // gix::Head usually has id() in recent versions
-    let head_id = head.id().ok_or_else(|| anyhow!("No HEAD"))?;
+    // An unborn HEAD (fresh `git init`) simply has no commits yet, that's not a failure
+    let Some(head_id) = head.id() else {
+        let entry = RepoEntry {
+            backend: Some(Backend::Git),
+            ..Default::default()
+        };
+        return Ok((entry, incomplete));
+    };
+    let head_id = head_id.detach();
+
+    let mut entry = RepoEntry {
+        tips: vec![head_id.to_string()],
+        backend: Some(Backend::Git),
+        ..Default::default()
+    };
+    // Checkouts and resets add reflog entries without new commits
+    if query.source.uses_reflog() {
+        entry.tips.push(reflog::tip(&head)?);
+    }
+    // Deepening or unshallowing adds history without moving HEAD
+    if let Some(shallow) = repo.shallow_commits()? {
+        let ids: Vec<String> = shallow.iter().map(ToString::to_string).collect();
+        entry.tips.push(format!("shallow:{}", ids.join(",")));
+    }
 
-    // Revwalk
-    let commit_graph = repo.rev_walk(Some(head_id.detach())).all()?;
+    // Nothing moved since last time, or only new commits were added on top
+    // of the cached tip, in which case only those need walking.
+    let mut hidden = Vec::new();
+    if let Some(cached) = cached
+        && cached.tips == entry.tips
+    {
+        let entry = RepoEntry {
+            backend: None,
+            visited: 0,
+            ..cached.clone()
+        };
+        return Ok((entry, incomplete));
+    }
+    if let Some(cached) = cached
+        && let [old_tip] = cached.tips.as_slice()
+        && let Ok(old_tip) = gix::ObjectId::from_hex(old_tip.as_bytes())
+        && repo
+            .merge_base(old_tip, head_id)
+            .is_ok_and(|base| base == old_tip)
+    {
+        hidden.push(old_tip);
+        entry.contributions = cached.contributions.clone();
+    }
+
+    let cutoff_date = query.cutoff();
+
+    // Revwalk, with nothing to walk when only counting the reflog. Commit dates
+    // bound the walk, and come from the commit-graph file when there is one, so
+    // only commits within the window get decoded.
+    let commit_graph = repo
+        .rev_walk(query.source.counts_commits().then_some(head_id))
+        .with_hidden(hidden)
+        .use_commit_graph(true)
+        .sorting(Sorting::ByCommitTimeCutoff {
+            order: CommitTimeOrder::NewestFirst,
+            seconds: cutoff_date.timestamp(),
+        })
+        .all()?;
 
-    let cutoff_date = Utc::now() - Duration::days(DAYS_IN_LAST_SIX_MONTHS);
+    let paths = path_matcher(&query.path_globs(path))?;
 
+    // Reflog entries have no diff to check against path or language filters
+    if query.source.uses_reflog() && paths.is_none() && query.lang.is_none() {
+        entry.contributions.extend(reflog::contributions(
+            &repo,
+            &head,
+            head_id,
+            query,
+            query.source.counts_commits(),
+        )?);
+    }
+
+    // Both caches are only needed when diffing, but creating them reads the index
+    // and attributes, so do it once per repo rather than once per commit.
+    let mut diff_caches = if query.needs_diff() || paths.is_some() {
+        Some((
+            repo.diff_resource_cache_for_tree_diff()?,
+            repo.diff_resource_cache_for_tree_diff()?,
+        ))
+    } else {
+        None
+    };
+
+    // The walk already stops at the boundary of a shallow clone, but a partial
+    // one may lack commits inside the window. Those are skipped, anything else
+    // going wrong is still an error.
+    let mut truncated = false;
     for info in commit_graph {
-        let info = info?;
-        let commit = info.object()?;
+        let info = match info {
+            Ok(info) => info,
+            Err(error) if partial && is_missing_commit(&error) => {
+                truncated = true;
+                continue;
+            }
+            Err(error) => return Err(error.into()),
+        };
+        entry.visited += 1;
+        let commit = match info.object() {
+            Ok(commit) => commit,
+            Err(gix::objs::find::existing::Error::NotFound { .. }) if partial => {
+                truncated = true;
+                continue;
+            }
+            Err(error) => return Err(error.into()),
+        };
         let author = commit.author()?;
 
-        // Compiler indicates author.time is &str in this context/version
-        let time_str: &str = author.time;
-        let seconds = time_str
-            .split_whitespace()
-            .next()
-            .and_then(|s| s.parse::<i64>().ok())
-            .unwrap_or(0);
-
-        // gix time is seconds since epoch
-        let datetime =
-            DateTime::from_timestamp(seconds, 0).ok_or_else(|| anyhow!("Invalid timestamp"))?;
+        let datetime = to_datetime(author.time()?)?;
 
+        // Rebased or cherry-picked commits keep their original author date
         if datetime < cutoff_date {
-            // Optimization: Stop traversing if we are too far back.
-            // Git history is topologically sorted (parents come after children),
-            // so once we hit a date older than our window, we can safely stop.
-            break;
+            continue;
         }
 
-        if author.email != email {
+        let credits = query.credits(
+            &author.email.to_str_lossy(),
+            &commit.message_raw()?.to_str_lossy(),
+        );
+        if credits.is_empty() {
             continue;
         }
 
-        let utc_date = datetime.date_naive();
-        *commits.entry(utc_date).or_insert(0) += 1;
+        // A partial clone may not have the trees to diff, such commits can't be
+        // checked against path filters and have no lines to attribute.
+        if let Some(paths) = &paths
+            && let Some((tree_cache, _)) = diff_caches.as_mut()
+        {
+            match touches_paths(&repo, &commit, tree_cache, paths) {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(_) if partial => continue,
+                Err(error) => return Err(error),
+            }
+        }
+
+        let mut lines = HashMap::new();
+        if query.needs_diff()
+            && let Some((tree_cache, blob_cache)) = diff_caches.as_mut()
+        {
+            lines = match changed_lines_by_language(&repo, &commit, tree_cache, blob_cache) {
+                Ok(lines) => lines,
+                Err(_) if partial => HashMap::new(),
+                Err(error) => return Err(error),
+            };
+            if let Some(lang) = query.lang
+                && !lines.contains_key(lang)
+            {
+                continue;
+            }
+        }
+
+        entry.contributions.extend(
+            credits
+                .into_iter()
+                .map(|credit| credit.contribution(datetime, lines.clone())),
+        );
+    }
+
+    if let Some(clone) = incomplete.as_mut() {
+        clone.truncated = truncated;
+    }
+    Ok((entry, incomplete))
+}
+
+/// Whether the walk failed on a commit that isn't in the object database at
+/// all, as opposed to one that's there but couldn't be read.
+fn is_missing_commit(error: &gix::revision::walk::iter::Error) -> bool {
+    matches!(
+        error,
+        gix::revision::walk::iter::Error::SimpleTraversal(
+            gix::traverse::commit::simple::Error::Find(
+                gix::objs::find::existing_iter::Error::NotFound { .. }
+            )
+        )
+    )
+}
+
+/// gix time is seconds since epoch plus the UTC offset, keep it in that offset.
+pub(crate) fn to_datetime(time: gix::date::Time) -> Result<DateTime<FixedOffset>> {
+    let offset = FixedOffset::east_opt(time.offset).ok_or_else(|| anyhow!("Invalid UTC offset"))?;
+    Ok(DateTime::from_timestamp(time.seconds, 0)
+        .ok_or_else(|| anyhow!("Invalid timestamp"))?
+        .with_timezone(&offset))
+}
+
+/// Match repo-relative paths like jj's `root-glob:`, where `*` stays within a directory.
+fn path_matcher(globs: &[&str]) -> Result<Option<GlobSet>> {
+    if globs.is_empty() {
+        return Ok(None);
     }
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(GlobBuilder::new(glob).literal_separator(true).build()?);
+    }
+    Ok(Some(builder.build()?))
+}
 
-    Ok(())
+fn first_parent_tree<'repo>(
+    repo: &'repo gix::Repository,
+    commit: &gix::Commit<'repo>,
+) -> Result<gix::Tree<'repo>> {
+    Ok(match commit.parent_ids().next() {
+        Some(id) => id.object()?.into_commit().tree()?,
+        None => repo.empty_tree(),
+    })
+}
+
+/// Whether the diff of `commit` against its first parent touches a path in `paths`.
+fn touches_paths(
+    repo: &gix::Repository,
+    commit: &gix::Commit<'_>,
+    tree_cache: &mut gix::diff::blob::Platform,
+    paths: &GlobSet,
+) -> Result<bool> {
+    let tree = commit.tree()?;
+    let mut touched = false;
+    let result = first_parent_tree(repo, commit)?
+        .changes()?
+        .options(|opts| {
+            opts.track_rewrites(None);
+        })
+        .for_each_to_obtain_tree_with_cache(&tree, tree_cache, |change| {
+            touched = change.entry_mode().is_blob_or_symlink()
+                && paths.is_match(change.location().to_str_lossy().as_ref());
+            Ok::<_, std::convert::Infallible>(if touched {
+                gix::object::tree::diff::Action::Cancel
+            } else {
+                gix::object::tree::diff::Action::Continue
+            })
+        });
+    // Stopping at the first match surfaces as a cancellation error
+    if touched {
+        return Ok(true);
+    }
+    result?;
+    Ok(false)
+}
+
+/// Diff `commit` against its first parent and sum up changed lines per language.
+fn changed_lines_by_language(
+    repo: &gix::Repository,
+    commit: &gix::Commit<'_>,
+    tree_cache: &mut gix::diff::blob::Platform,
+    blob_cache: &mut gix::diff::blob::Platform,
+) -> Result<HashMap<String, u64>> {
+    let tree = commit.tree()?;
+    let parent_tree = first_parent_tree(repo, commit)?;
+
+    let mut lines = HashMap::new();
+    parent_tree
+        .changes()?
+        .options(|opts| {
+            opts.track_rewrites(None);
+        })
+        .for_each_to_obtain_tree_with_cache(&tree, tree_cache, |change| {
+            if !change.entry_mode().is_blob() {
+                return Ok::<_, std::convert::Infallible>(
+                    gix::object::tree::diff::Action::Continue,
+                );
+            }
+            if let Some(lang) = languages::detect(&change.location().to_str_lossy()) {
+                // Binary files still count towards the language, just without lines.
+                let changed = change
+                    .diff(blob_cache)
+                    .ok()
+                    .and_then(|mut platform| platform.line_counts().ok())
+                    .flatten()
+                    .map_or(0, |counts| u64::from(counts.insertions + counts.removals));
+                *lines.entry(lang.to_string()).or_insert(0) += changed;
+            }
+            blob_cache.clear_resource_cache_keep_allocation();
+            Ok(gix::object::tree::diff::Action::Continue)
+        })?;
+
+    Ok(lines)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::{commit_file, git, git_at, init_repo};
     use tempfile::tempdir;
 
     #[test]
@@ -147,21 +846,21 @@ mod tests {
         assert!(status.success(), "git init failed");
 
         let status = std::process::Command::new("git")
-            .args(&["config", "user.email", email])
+            .args(["config", "user.email", email])
             .current_dir(&repo_path)
             .status()?;
 
         assert!(status.success(), "git config email failed");
 
         let status = std::process::Command::new("git")
-            .args(&["config", "user.name", "Test User"])
+            .args(["config", "user.name", "Test User"])
             .current_dir(&repo_path)
             .status()?;
 
         assert!(status.success(), "git config name failed");
 
         let status = std::process::Command::new("git")
-            .args(&["config", "commit.gpgsign", "false"])
+            .args(["config", "commit.gpgsign", "false"])
             .current_dir(&repo_path)
             .status()?;
 
@@ -172,14 +871,14 @@ mod tests {
         std::fs::write(repo_path.join("file"), "content")?;
 
         let status = std::process::Command::new("git")
-            .args(&["add", "file"])
+            .args(["add", "file"])
             .current_dir(&repo_path)
             .status()?;
 
         assert!(status.success(), "git add failed");
 
         let output = std::process::Command::new("git")
-            .args(&["commit", "-m", "msg"])
+            .args(["commit", "-m", "msg"])
             .current_dir(&repo_path)
             .output()?;
 
@@ -191,61 +890,639 @@ mod tests {
         }
 
         let repos = vec![repo_path];
-        let stats = process_repositories(repos, email);
+        let stats = process_repositories(repos, &Query::new(email), &mut StatsCache::default());
 
-        let today = Utc::now().date_naive();
-        assert_eq!(stats.get(&today), Some(&1));
+        let today = Local::now().date_naive();
+        assert_eq!(stats.commits.get(&today), Some(&1));
 
         Ok(())
     }
-}
 
-fn process_jj(path: &Path, email: &str, commits: &mut CommitCounts) -> Result<()> {
-    use std::process::Command;
+    fn commit_file_at(repo_path: &Path, file: &str, date: DateTime<FixedOffset>) {
+        std::fs::write(repo_path.join(file), file).unwrap();
+        git(repo_path, &["add", file]);
+        git_at(repo_path, &["commit", "-m", file], &date.to_rfc3339());
+    }
+
+    #[test]
+    fn test_language_breakdown() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("polyglot");
+        let email = "test@example.com";
+        init_repo(&repo_path, email);
 
-    // Use a specific date format: YYYY-MM-DD
+        commit_file(&repo_path, "main.rs", "fn main() {}\n");
+        commit_file(&repo_path, "lib.rs", "pub fn a() {}\npub fn b() {}\n");
+        commit_file(&repo_path, "README.md", "# Title\n");
 
-    let output = Command::new("jj")
-        .arg("log")
-        .arg("--no-graph")
-        .arg("-r")
-        .arg("::@") // Ancestors of HEAD
-        .arg("-T")
-        .arg(r#"author.email() ++ "|" ++ author.timestamp().format("%Y-%m-%d") ++ "\n""#)
-        .current_dir(path)
-        .output()?;
+        let query = Query {
+            languages: true,
+            ..Query::new(email)
+        };
+        let stats =
+            process_repositories(vec![repo_path.clone()], &query, &mut StatsCache::default());
+        assert_eq!(
+            stats.languages.get("Rust"),
+            Some(&LanguageCount {
+                commits: 2,
+                lines: 3
+            })
+        );
+        assert_eq!(stats.languages.get("Markdown").map(|c| c.commits), Some(1));
 
-    if !output.status.success() {
-        return Err(anyhow!("jj command failed"));
+        let query = Query {
+            lang: Some("Rust"),
+            ..Query::new(email)
+        };
+        let stats = process_repositories(vec![repo_path], &query, &mut StatsCache::default());
+        let today = Local::now().date_naive();
+        assert_eq!(stats.commits.get(&today), Some(&2));
+
+        Ok(())
     }
 
-    let stdout = String::from_utf8(output.stdout)?;
+    #[test]
+    fn test_punchcard_uses_author_timezone() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("night-owl");
+        let email = "test@example.com";
+        init_repo(&repo_path, email);
 
-    let cutoff_date = (Utc::now() - Duration::days(DAYS_IN_LAST_SIX_MONTHS)).date_naive();
+        // 23:30 in Tokyo is mid-afternoon UTC, the punchcard should show the former
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        let date = (Utc::now() - Duration::days(2))
+            .with_timezone(&tokyo)
+            .with_hour(23)
+            .and_then(|d| d.with_minute(30))
+            .unwrap();
+        commit_file_at(&repo_path, "late.txt", date);
 
-    for line in stdout.lines() {
-        let parts: Vec<&str> = line.split('|').collect();
+        let stats = process_repositories(
+            vec![repo_path],
+            &Query::new(email),
+            &mut StatsCache::default(),
+        );
+        let weekday = date.weekday().num_days_from_sunday() as usize;
+        assert_eq!(stats.punchcard[weekday][23], 1);
+        assert_eq!(stats.punchcard.iter().flatten().sum::<i32>(), 1);
+        assert_eq!(
+            stats.commits.get(&date.with_timezone(&Local).date_naive()),
+            Some(&1)
+        );
 
-        if parts.len() < 2 {
-            continue;
-        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_days_use_the_local_calendar() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("far-west");
+        let email = "test@example.com";
+        init_repo(&repo_path, email);
 
-        let commit_email = parts[0].trim();
+        // Authored a second ago at UTC-10, which is a different UTC date for half the day
+        let hawaii = FixedOffset::west_opt(10 * 3600).unwrap();
+        let date = (Utc::now() - Duration::seconds(1)).with_timezone(&hawaii);
+        commit_file_at(&repo_path, "late.txt", date);
 
-        let date_str = parts[1].trim();
+        let stats = process_repositories(
+            vec![repo_path],
+            &Query::new(email),
+            &mut StatsCache::default(),
+        );
+        let local_date = date.with_timezone(&Local).date_naive();
+        assert_eq!(stats.commits.keys().collect::<Vec<_>>(), vec![&local_date]);
+        assert!(local_date <= Local::now().date_naive());
 
-        if commit_email != email {
-            continue;
+        Ok(())
+    }
+
+    #[test]
+    fn test_reports_failed_repos() -> Result<()> {
+        let dir = tempdir()?;
+        let email = "test@example.com";
+
+        let good = dir.path().join("good");
+        init_repo(&good, email);
+        commit_file(&good, "file", "content");
+
+        // A `.git` directory with nothing in it can't be opened
+        let corrupt = dir.path().join("corrupt");
+        std::fs::create_dir_all(corrupt.join(".git"))?;
+
+        let stats = process_repositories(
+            vec![good, corrupt.clone()],
+            &Query::new(email),
+            &mut StatsCache::default(),
+        );
+        assert_eq!(stats.commits.values().sum::<i32>(), 1);
+        assert_eq!(stats.failures.len(), 1);
+        assert_eq!(stats.failures[0].path, corrupt);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_old_head_is_not_stale() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("same-branch");
+        let email = "test@example.com";
+        init_repo(&repo_path, email);
+        commit_file(&repo_path, "file", "content");
+
+        // Last checkout was long ago, but the branch still moved today
+        let long_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(400 * 86400);
+        std::fs::File::open(repo_path.join(".git").join("HEAD"))?.set_modified(long_ago)?;
+
+        let stats = process_repositories(
+            vec![repo_path],
+            &Query::new(email),
+            &mut StatsCache::default(),
+        );
+        assert!(stats.skipped.is_empty());
+        assert_eq!(stats.commits.values().sum::<i32>(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_walks_only_new_commits() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("cached");
+        let email = "test@example.com";
+        init_repo(&repo_path, email);
+        commit_file(&repo_path, "one", "1");
+
+        let query = Query::new(email);
+        let mut cache = StatsCache::default();
+        let stats = process_repositories(vec![repo_path.clone()], &query, &mut cache);
+        assert_eq!(stats.commits.values().sum::<i32>(), 1);
+
+        // Tamper with the cached contribution: if it survives, the old commit wasn't walked again
+        let cached_time = cache
+            .get(&repo_path, &query.fingerprint())
+            .unwrap()
+            .contributions[0]
+            .time;
+        let earlier = cached_time - Duration::days(1);
+        cache.repos.get_mut(&repo_path).unwrap()[0].contributions[0].time = earlier;
+
+        commit_file(&repo_path, "two", "2");
+        let stats = process_repositories(vec![repo_path.clone()], &query, &mut cache);
+        assert_eq!(stats.commits.values().sum::<i32>(), 2);
+        assert_eq!(
+            stats
+                .commits
+                .get(&earlier.with_timezone(&Local).date_naive()),
+            Some(&1)
+        );
+        assert_eq!(cache.repos[&repo_path][0].contributions.len(), 2);
+        assert_eq!(stats.profiles[0].backend, Some(Backend::Git));
+        assert_eq!(stats.profiles[0].visited, 1);
+
+        // Nothing new, so nothing is walked
+        let stats = process_repositories(vec![repo_path.clone()], &query, &mut cache);
+        assert_eq!(stats.commits.values().sum::<i32>(), 2);
+        assert_eq!(stats.profiles[0].backend, None);
+        assert_eq!(stats.profiles[0].visited, 0);
+
+        // A different query can't reuse the entry
+        let other = Query::new("someone@else.com");
+        let stats = process_repositories(vec![repo_path.clone()], &other, &mut cache);
+        assert!(stats.commits.is_empty());
+        assert_eq!(stats.profiles[0].backend, Some(Backend::Git));
+
+        // ...but doesn't evict it either
+        let stats = process_repositories(vec![repo_path], &query, &mut cache);
+        assert_eq!(stats.commits.values().sum::<i32>(), 2);
+        assert_eq!(stats.profiles[0].backend, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_rewalks_rewritten_history() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("rewritten");
+        let email = "test@example.com";
+        init_repo(&repo_path, email);
+        commit_file(&repo_path, "one", "1");
+        commit_file(&repo_path, "two", "2");
+
+        let query = Query::new(email);
+        let mut cache = StatsCache::default();
+        let stats = process_repositories(vec![repo_path.clone()], &query, &mut cache);
+        assert_eq!(stats.commits.values().sum::<i32>(), 2);
+
+        // Dropping a commit means the old tip is no longer an ancestor of HEAD
+        git(&repo_path, &["reset", "--hard", "HEAD~1"]);
+        commit_file(&repo_path, "three", "3");
+        let stats = process_repositories(vec![repo_path], &query, &mut cache);
+        assert_eq!(stats.commits.values().sum::<i32>(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_co_author_emails() {
+        assert_eq!(
+            co_author_emails(
+                "Fix it\n\nSome details\n\nCo-authored-by: Test User <Test@Example.com>\n"
+            ),
+            vec!["Test@Example.com"]
+        );
+        assert_eq!(
+            co_author_emails(
+                "Fix it\n\nSigned-off-by: A <a@example.com>\nco-authored-by: T <test@example.com>"
+            ),
+            vec!["test@example.com"]
+        );
+        // Only the trailer block counts, not mentions in the body
+        assert!(
+            co_author_emails(
+                "Co-authored-by: T <test@example.com>\n\nSigned-off-by: A <a@example.com>"
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_credits() {
+        let message = "Pair\n\nCo-authored-by: T <Test@Example.com>";
+        let query = Query::new("test@example.com");
+        assert_eq!(
+            query.credits("other@example.com", message),
+            vec![Credit {
+                author: None,
+                co_authored: true
+            }]
+        );
+        assert!(query.credits("other@example.com", "Solo").is_empty());
+        // Emails compare the same way whether they're the author or a co-author
+        assert_eq!(
+            query.credits("Test@Example.com", "Solo"),
+            vec![Credit {
+                author: None,
+                co_authored: false
+            }]
+        );
+
+        let team = Query {
+            all_authors: true,
+            ..query
+        };
+        assert_eq!(
+            team.credits("Other@Example.com", message),
+            vec![
+                Credit {
+                    author: Some("other@example.com".to_string()),
+                    co_authored: false
+                },
+                Credit {
+                    author: Some("test@example.com".to_string()),
+                    co_authored: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_co_authored_commits() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("pairing");
+        let email = "test@example.com";
+        init_repo(&repo_path, "other@example.com");
+        commit_file(&repo_path, "solo", "1");
+        std::fs::write(repo_path.join("paired"), "2")?;
+        git(&repo_path, &["add", "paired"]);
+        git(
+            &repo_path,
+            &[
+                "commit",
+                "-m",
+                "Pair on it",
+                "-m",
+                "Co-authored-by: Test User <test@example.com>",
+            ],
+        );
+
+        let stats = process_repositories(
+            vec![repo_path.clone()],
+            &Query::new(email),
+            &mut StatsCache::default(),
+        );
+        assert_eq!(stats.commits.values().sum::<i32>(), 1);
+        assert_eq!(stats.co_authored, 1);
+
+        let query = Query {
+            co_authors: false,
+            ..Query::new(email)
+        };
+        let stats = process_repositories(vec![repo_path], &query, &mut StatsCache::default());
+        assert!(stats.commits.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_filters() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("filtered");
+        let email = "test@example.com";
+        init_repo(&repo_path, email);
+        for message in [
+            "PROJ-1 Add parser",
+            "WIP",
+            "fixup! PROJ-1 Add parser",
+            "Tidy up",
+        ] {
+            git(&repo_path, &["commit", "--allow-empty", "-m", message]);
         }
 
-        match NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-            Ok(date) if date >= cutoff_date => {
-                *commits.entry(date).or_insert(0) += 1;
-            }
+        let count = |query: &Query| {
+            let stats =
+                process_repositories(vec![repo_path.clone()], query, &mut StatsCache::default());
+            stats.commits.values().sum::<i32>()
+        };
+        let exclude = Query {
+            exclude_grep: Some(Regex::new("^(WIP|fixup!)")?),
+            ..Query::new(email)
+        };
+        assert_eq!(count(&exclude), 2);
+        let grep = Query {
+            grep: Some(Regex::new(r"PROJ-\d+")?),
+            ..exclude.clone()
+        };
+        assert_eq!(count(&grep), 1);
+
+        // Different patterns must not share cache entries
+        assert_ne!(exclude.fingerprint(), grep.fingerprint());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_filter_from_str() {
+        let filter: PathFilter = "monorepo=services/api/**".parse().unwrap();
+        assert_eq!(filter.repo.as_deref(), Some("monorepo"));
+        assert_eq!(filter.glob, "services/api/**");
+        assert!(filter.applies_to(Path::new("/home/me/Repos/monorepo")));
+        assert!(!filter.applies_to(Path::new("/home/me/Repos/other")));
+
+        let filter: PathFilter = "docs/*.md".parse().unwrap();
+        assert_eq!(filter.repo, None);
+        assert!(filter.applies_to(Path::new("/anywhere")));
+
+        assert!("monorepo=".parse::<PathFilter>().is_err());
+    }
+
+    #[test]
+    fn test_path_filters() -> Result<()> {
+        let dir = tempdir()?;
+        let email = "test@example.com";
+        let monorepo = dir.path().join("monorepo");
+        init_repo(&monorepo, email);
+        std::fs::create_dir_all(monorepo.join("services/api/src"))?;
+        std::fs::create_dir_all(monorepo.join("services/web"))?;
+        commit_file(&monorepo, "services/api/src/main.rs", "fn main() {}");
+        commit_file(&monorepo, "services/web/index.html", "<html>");
+        commit_file(&monorepo, "README.md", "# Monorepo");
+        let other = dir.path().join("other");
+        init_repo(&other, email);
+        commit_file(&other, "README.md", "# Other");
+
+        let count = |paths: &[&str]| -> Result<i32> {
+            let query = Query {
+                paths: paths.iter().map(|p| p.parse()).collect::<Result<_>>()?,
+                ..Query::new(email)
+            };
+            let repos = vec![monorepo.clone(), other.clone()];
+            let stats = process_repositories(repos, &query, &mut StatsCache::default());
+            Ok(stats.commits.values().sum::<i32>())
+        };
+        assert_eq!(count(&["services/api/**"])?, 1);
+        // `*` doesn't cross directories
+        assert_eq!(count(&["services/*"])?, 0);
+        assert_eq!(count(&["services/**", "*.md"])?, 4);
+        // A per-repo filter leaves other repos alone
+        assert_eq!(count(&["monorepo=services/api/**"])?, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reflog_activity() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("rebaser");
+        let email = "test@example.com";
+        init_repo(&repo_path, email);
+        commit_file(&repo_path, "one", "1");
+        commit_file(&repo_path, "two", "2");
+        git(&repo_path, &["commit", "--amend", "-m", "two v2"]);
+        git(&repo_path, &["checkout", "-b", "topic"]);
+        // Drops both versions of the second commit from the history
+        git(&repo_path, &["reset", "--hard", "HEAD~1"]);
+
+        let run = |source: ActivitySource| {
+            let query = Query {
+                source,
+                ..Query::new(email)
+            };
+            process_repositories(vec![repo_path.clone()], &query, &mut StatsCache::default())
+        };
+
+        let stats = run(ActivitySource::Commits);
+        assert_eq!(stats.commits.values().sum::<i32>(), 1);
+        assert!(stats.events.is_empty());
+
+        let stats = run(ActivitySource::Reflog);
+        assert_eq!(stats.commits.values().sum::<i32>(), 5);
+        assert_eq!(stats.events.get(&reflog::Event::Commit), Some(&2));
+
+        // The reachable commit isn't counted again from the reflog
+        let stats = run(ActivitySource::Both);
+        assert_eq!(stats.commits.values().sum::<i32>(), 5);
+        assert_eq!(
+            stats.events,
+            [
+                (reflog::Event::Commit, 1),
+                (reflog::Event::Amend, 1),
+                (reflog::Event::Checkout, 1),
+                (reflog::Event::Reset, 1),
+            ]
+            .into_iter()
+            .collect()
+        );
+
+        Ok(())
+    }
 
-            _ => {}
+    #[test]
+    fn test_shallow_clone() -> Result<()> {
+        let dir = tempdir()?;
+        let origin = dir.path().join("origin");
+        let email = "test@example.com";
+        init_repo(&origin, email);
+        for file in ["one", "two", "three"] {
+            commit_file(&origin, file, file);
         }
+        let url = format!("file://{}", origin.display());
+        let clone = dir.path().join("clone");
+        git(
+            dir.path(),
+            &["clone", "--depth", "2", &url, clone.to_str().unwrap()],
+        );
+
+        let query = Query::new(email);
+        let mut cache = StatsCache::default();
+        let stats = process_repositories(vec![clone.clone()], &query, &mut cache);
+        assert!(stats.failures.is_empty());
+        assert_eq!(stats.commits.values().sum::<i32>(), 2);
+        assert_eq!(
+            stats.incomplete,
+            vec![IncompleteClone {
+                path: clone.clone(),
+                shallow: true,
+                partial: false,
+                truncated: false,
+            }]
+        );
+
+        // Fetching the rest of the history doesn't move HEAD, but isn't missed
+        git(&clone, &["fetch", "--unshallow"]);
+        let stats = process_repositories(vec![clone], &query, &mut cache);
+        assert_eq!(stats.commits.values().sum::<i32>(), 3);
+        assert!(stats.incomplete.is_empty());
+
+        Ok(())
     }
 
-    Ok(())
+    #[test]
+    fn test_partial_clone() -> Result<()> {
+        let dir = tempdir()?;
+        let origin = dir.path().join("origin");
+        let email = "test@example.com";
+        init_repo(&origin, email);
+        git(&origin, &["config", "uploadpack.allowFilter", "true"]);
+        commit_file(&origin, "main.rs", "fn main() {}\n");
+        commit_file(&origin, "lib.rs", "pub fn a() {}\n");
+        commit_file(&origin, "README.md", "# Readme\n");
+        let url = format!("file://{}", origin.display());
+        let clone = dir.path().join("clone");
+        git(
+            dir.path(),
+            &["clone", "--filter=tree:0", &url, clone.to_str().unwrap()],
+        );
+
+        // Older trees aren't there to diff, their commits still count
+        let query = Query {
+            languages: true,
+            ..Query::new(email)
+        };
+        let stats = process_repositories(vec![clone.clone()], &query, &mut StatsCache::default());
+        assert!(stats.failures.is_empty(), "{:?}", stats.failures);
+        assert_eq!(stats.commits.values().sum::<i32>(), 3);
+        assert_eq!(
+            stats.incomplete,
+            vec![IncompleteClone {
+                path: clone,
+                shallow: false,
+                partial: true,
+                truncated: false,
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_commits() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("holes");
+        let email = "test@example.com";
+        init_repo(&repo_path, email);
+        commit_file(&repo_path, "one", "1");
+        commit_file(&repo_path, "two", "2");
+        commit_file(&repo_path, "three", "3");
+        // The commit-graph still knows the middle commit once its object is gone
+        git(&repo_path, &["commit-graph", "write", "--reachable"]);
+        let output = std::process::Command::new("git")
+            .args(["rev-parse", "HEAD~1"])
+            .current_dir(&repo_path)
+            .output()?;
+        let id = String::from_utf8(output.stdout)?.trim().to_string();
+        std::fs::remove_file(
+            repo_path
+                .join(".git")
+                .join("objects")
+                .join(&id[..2])
+                .join(&id[2..]),
+        )?;
+
+        // A missing commit is corruption in a full clone
+        let stats = process_repositories(
+            vec![repo_path.clone()],
+            &Query::new(email),
+            &mut StatsCache::default(),
+        );
+        assert_eq!(stats.failures.len(), 1);
+
+        // ...but expected in a partial one, where it's skipped and reported
+        git(&repo_path, &["remote", "add", "origin", "file:///nowhere"]);
+        git(&repo_path, &["config", "remote.origin.promisor", "true"]);
+        let stats = process_repositories(
+            vec![repo_path.clone()],
+            &Query::new(email),
+            &mut StatsCache::default(),
+        );
+        assert!(stats.failures.is_empty(), "{:?}", stats.failures);
+        assert_eq!(stats.commits.values().sum::<i32>(), 2);
+        assert_eq!(
+            stats.incomplete,
+            vec![IncompleteClone {
+                path: repo_path,
+                shallow: false,
+                partial: true,
+                truncated: true,
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_walk_bounded_by_commit_date() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_path = dir.path().join("graph");
+        let email = "test@example.com";
+        init_repo(&repo_path, email);
+        let now = Utc::now().fixed_offset();
+        let old = now - Duration::days(DAYS_IN_LAST_SIX_MONTHS + 30);
+        commit_file_at(&repo_path, "ancient", old - Duration::days(1));
+        commit_file_at(&repo_path, "old", old);
+        commit_file_at(&repo_path, "recent", now - Duration::days(1));
+        // Cherry-picked today, but authored long ago
+        std::fs::write(repo_path.join("picked"), "picked")?;
+        git(&repo_path, &["add", "picked"]);
+        let output = std::process::Command::new("git")
+            .args(["commit", "-m", "picked"])
+            .env("GIT_AUTHOR_DATE", old.to_rfc3339())
+            .env("GIT_COMMITTER_DATE", now.to_rfc3339())
+            .current_dir(&repo_path)
+            .output()?;
+        assert!(output.status.success(), "git commit failed");
+        git(&repo_path, &["commit-graph", "write", "--reachable"]);
+
+        let stats = process_repositories(
+            vec![repo_path],
+            &Query::new(email),
+            &mut StatsCache::default(),
+        );
+        // The old author date doesn't end the walk before the recent commit
+        assert_eq!(stats.commits.values().sum::<i32>(), 1);
+        // Commits from before the window aren't visited at all
+        assert_eq!(stats.profiles[0].visited, 2);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,174 @@
+use crate::jj;
+use crate::stats::{self, RepoFailure};
+use anyhow::Result;
+use gix::bstr::ByteSlice;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// An operation that has to be finished or aborted before moving on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Rebase,
+    Merge,
+    CherryPick,
+    Revert,
+    Bisect,
+    ApplyMailbox,
+    /// jj records conflicts instead of stopping, so they're what's left to resolve.
+    Conflict,
+}
+
+impl Operation {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Operation::Rebase => "rebase",
+            Operation::Merge => "merge",
+            Operation::CherryPick => "cherry-pick",
+            Operation::Revert => "revert",
+            Operation::Bisect => "bisect",
+            Operation::ApplyMailbox => "am",
+            Operation::Conflict => "conflict",
+        }
+    }
+}
+
+/// The state of a repository's working copy.
+#[derive(Debug, Clone, Default)]
+pub struct RepoStatus {
+    pub path: PathBuf,
+    /// The checked out branch or, for jj, the bookmark on the working copy or its
+    /// parent. `None` when detached.
+    pub branch: Option<String>,
+    /// Tracked files with staged or unstaged changes.
+    pub changed: usize,
+    pub untracked: usize,
+    pub operation: Option<Operation>,
+}
+
+impl RepoStatus {
+    pub fn needs_attention(&self) -> bool {
+        self.changed > 0 || self.untracked > 0 || self.operation.is_some()
+    }
+}
+
+/// Check every repository, keeping the ones with uncommitted work or an
+/// unfinished operation.
+pub fn check(repos: Vec<PathBuf>) -> (Vec<RepoStatus>, Vec<RepoFailure>) {
+    stats::check_repos(repos, |path| {
+        let status = if path.join(".git").exists() {
+            git_status(path)?
+        } else if path.join(".jj").exists() {
+            jj::status(path)?
+        } else {
+            return Ok(None);
+        };
+        Ok(status.needs_attention().then(|| RepoStatus {
+            path: path.to_path_buf(),
+            ..status
+        }))
+    })
+}
+
+fn git_status(path: &Path) -> Result<RepoStatus> {
+    let repo = gix::open(path)?;
+    let branch = repo
+        .head_name()?
+        .map(|name| name.shorten().to_str_lossy().into_owned());
+
+    let operation = repo.state().map(|state| {
+        use gix::state::InProgress;
+        match state {
+            InProgress::Rebase | InProgress::RebaseInteractive => Operation::Rebase,
+            InProgress::Merge => Operation::Merge,
+            InProgress::CherryPick | InProgress::CherryPickSequence => Operation::CherryPick,
+            InProgress::Revert | InProgress::RevertSequence => Operation::Revert,
+            InProgress::Bisect => Operation::Bisect,
+            InProgress::ApplyMailbox | InProgress::ApplyMailboxRebase => Operation::ApplyMailbox,
+        }
+    });
+
+    // A file both staged and modified again only counts once
+    let mut changed = HashSet::new();
+    let mut untracked = 0;
+    if repo.workdir().is_some() {
+        for item in repo.status(gix::progress::Discard)?.into_iter(None)? {
+            let item = item?;
+            match &item {
+                gix::status::Item::IndexWorktree(
+                    gix::status::index_worktree::Item::DirectoryContents { entry, .. },
+                ) => {
+                    if entry.status == gix::dir::entry::Status::Untracked {
+                        untracked += 1;
+                    }
+                }
+                _ => {
+                    changed.insert(item.location().to_owned());
+                }
+            }
+        }
+    }
+
+    Ok(RepoStatus {
+        path: path.to_path_buf(),
+        branch,
+        changed: changed.len(),
+        untracked,
+        operation,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{git, init_repo};
+    use tempfile::tempdir;
+
+    fn init(repo_path: &Path) {
+        init_repo(repo_path, "test@example.com");
+        std::fs::write(repo_path.join("file"), "one").unwrap();
+        git(repo_path, &["add", "file"]);
+        git(repo_path, &["commit", "-m", "Initial"]);
+    }
+
+    #[test]
+    fn test_check_reports_dirty_repos() {
+        let dir = tempdir().unwrap();
+        let clean = dir.path().join("clean");
+        init(&clean);
+
+        let dirty = dir.path().join("dirty");
+        init(&dirty);
+        std::fs::write(dirty.join("file"), "two").unwrap();
+        git(&dirty, &["add", "file"]);
+        std::fs::write(dirty.join("file"), "three").unwrap();
+        std::fs::write(dirty.join("notes.txt"), "todo").unwrap();
+
+        let merging = dir.path().join("merging");
+        init(&merging);
+        git(&merging, &["checkout", "-b", "topic"]);
+        std::fs::write(merging.join("file"), "topic").unwrap();
+        git(&merging, &["commit", "-am", "Topic"]);
+        git(&merging, &["checkout", "main"]);
+        std::fs::write(merging.join("file"), "main").unwrap();
+        git(&merging, &["commit", "-am", "Main"]);
+        // Conflicts, leaving the merge in progress
+        let _ = std::process::Command::new("git")
+            .args(["merge", "topic"])
+            .current_dir(&merging)
+            .output();
+
+        let (found, failures) = check(vec![clean, dirty.clone(), merging.clone()]);
+        assert!(failures.is_empty());
+        assert_eq!(found.len(), 2);
+
+        assert_eq!(found[0].path, dirty);
+        assert_eq!(found[0].branch.as_deref(), Some("main"));
+        assert_eq!(found[0].changed, 1);
+        assert_eq!(found[0].untracked, 1);
+        assert_eq!(found[0].operation, None);
+
+        assert_eq!(found[1].path, merging);
+        assert_eq!(found[1].operation, Some(Operation::Merge));
+        assert_eq!(found[1].changed, 1);
+    }
+}
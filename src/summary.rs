@@ -0,0 +1,172 @@
+use crate::stats::CommitCounts;
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+
+/// A run of consecutive days with at least one contribution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Streak {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub days: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub total: i32,
+    /// Streak ending today, or yesterday if nothing has been committed yet today.
+    pub current_streak: Option<Streak>,
+    pub longest_streak: Option<Streak>,
+    pub busiest_day: Option<(NaiveDate, i32)>,
+    pub active_days: i64,
+    pub total_days: i64,
+}
+
+impl Summary {
+    pub fn active_percentage(&self) -> f64 {
+        if self.total_days == 0 {
+            return 0.0;
+        }
+        self.active_days as f64 * 100.0 / self.total_days as f64
+    }
+
+    pub fn mean_per_active_day(&self) -> f64 {
+        if self.active_days == 0 {
+            return 0.0;
+        }
+        self.total as f64 / self.active_days as f64
+    }
+}
+
+/// Summarize the contributions between `start` and `end` (both inclusive).
+pub fn summarize(commits: &CommitCounts, start: NaiveDate, end: NaiveDate) -> Summary {
+    let count = |date: NaiveDate| commits.get(&date).copied().unwrap_or(0);
+
+    let mut total = 0;
+    let mut active_days = 0;
+    let mut busiest_day: Option<(NaiveDate, i32)> = None;
+    let mut longest_streak: Option<Streak> = None;
+    let mut streak: Option<Streak> = None;
+
+    let mut date = start;
+    while date <= end {
+        let n = count(date);
+        if n > 0 {
+            total += n;
+            active_days += 1;
+            // Ties go to the most recent day
+            if busiest_day.is_none_or(|(_, max)| n >= max) {
+                busiest_day = Some((date, n));
+            }
+
+            let current = match streak {
+                Some(s) => Streak {
+                    end: date,
+                    days: s.days + 1,
+                    ..s
+                },
+                None => Streak {
+                    start: date,
+                    end: date,
+                    days: 1,
+                },
+            };
+            if longest_streak.is_none_or(|longest| current.days >= longest.days) {
+                longest_streak = Some(current);
+            }
+            streak = Some(current);
+        } else {
+            streak = None;
+        }
+        date += Duration::days(1);
+    }
+
+    // An empty `end` day doesn't break the current streak yet.
+    let current_streak = streak.or_else(|| {
+        let mut days = 0;
+        let mut date = end - Duration::days(1);
+        while date >= start && count(date) > 0 {
+            days += 1;
+            date -= Duration::days(1);
+        }
+        (days > 0).then(|| Streak {
+            start: date + Duration::days(1),
+            end: end - Duration::days(1),
+            days,
+        })
+    });
+
+    Summary {
+        total,
+        current_streak,
+        longest_streak,
+        busiest_day,
+        active_days,
+        total_days: (end - start).num_days() + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, d).unwrap()
+    }
+
+    #[test]
+    fn test_summarize() {
+        let commits: CommitCounts = [
+            (day(2), 1),
+            (day(3), 4),
+            (day(4), 2),
+            (day(8), 4),
+            (day(9), 1),
+        ]
+        .into_iter()
+        .collect();
+
+        let summary = summarize(&commits, day(1), day(10));
+        assert_eq!(summary.total, 12);
+        assert_eq!(summary.active_days, 5);
+        assert_eq!(summary.total_days, 10);
+        assert_eq!(summary.busiest_day, Some((day(8), 4)));
+        assert_eq!(
+            summary.longest_streak,
+            Some(Streak {
+                start: day(2),
+                end: day(4),
+                days: 3
+            })
+        );
+        // Nothing on the last day yet, so the streak through yesterday still counts
+        assert_eq!(
+            summary.current_streak,
+            Some(Streak {
+                start: day(8),
+                end: day(9),
+                days: 2
+            })
+        );
+        assert_eq!(summary.active_percentage(), 50.0);
+        assert_eq!(summary.mean_per_active_day(), 2.4);
+    }
+
+    #[test]
+    fn test_summarize_broken_streak() {
+        let commits: CommitCounts = [(day(2), 1), (day(3), 1)].into_iter().collect();
+
+        let summary = summarize(&commits, day(1), day(10));
+        assert_eq!(summary.current_streak, None);
+        assert_eq!(summary.longest_streak.map(|s| s.days), Some(2));
+    }
+
+    #[test]
+    fn test_summarize_empty() {
+        let summary = summarize(&CommitCounts::new(), day(1), day(10));
+        assert_eq!(summary.total, 0);
+        assert_eq!(summary.current_streak, None);
+        assert_eq!(summary.longest_streak, None);
+        assert_eq!(summary.busiest_day, None);
+        assert_eq!(summary.mean_per_active_day(), 0.0);
+    }
+}
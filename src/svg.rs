@@ -0,0 +1,169 @@
+use crate::stats::CommitCounts;
+use crate::ui::{self, Level};
+use chrono::{Datelike, Duration, NaiveDate};
+use std::fmt::Write;
+
+const CELL: i64 = 11;
+const STEP: i64 = CELL + 3;
+/// Room for the weekday labels on the left and the month labels on top.
+const LEFT: i64 = 32;
+const TOP: i64 = 20;
+const LEGEND_HEIGHT: i64 = 24;
+const FONT: &str = "font-family=\"-apple-system,Segoe UI,Helvetica,Arial,sans-serif\" font-size=\"10\" fill=\"#57606a\"";
+
+/// Colors matching the terminal grid, with empty days as outlined blanks.
+fn fill(level: Level) -> &'static str {
+    match level {
+        Level::Empty => "#ffffff",
+        Level::Low => "#d4d4d4",
+        Level::Medium => "#e3b341",
+        Level::High => "#2da44e",
+    }
+}
+
+fn commits_label(count: i32) -> String {
+    if count == 1 {
+        "1 commit".to_string()
+    } else {
+        format!("{} commits", count)
+    }
+}
+
+fn cell(svg: &mut String, x: i64, y: i64, level: Level, title: &str) {
+    let _ = writeln!(
+        svg,
+        "  <rect x=\"{}\" y=\"{}\" width=\"{CELL}\" height=\"{CELL}\" rx=\"2\" fill=\"{}\" stroke=\"#d0d7de\" stroke-width=\"0.5\"><title>{}</title></rect>",
+        x,
+        y,
+        fill(level),
+        title
+    );
+}
+
+/// Render the contribution grid for the weeks from `start` through `end` as a
+/// standalone SVG, laid out like `ui::print_stats` with a legend below.
+pub fn render(commits: &CommitCounts, start: NaiveDate, end: NaiveDate) -> String {
+    let grid_start = ui::week_start(start);
+    let weeks = ((end - grid_start).num_days() + 7) / 7;
+    let width = LEFT + weeks * STEP;
+    let height = TOP + 7 * STEP + LEGEND_HEIGHT;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">"
+    );
+    let _ = writeln!(
+        svg,
+        "  <rect width=\"{width}\" height=\"{height}\" fill=\"#ffffff\"/>"
+    );
+
+    // Month labels over the first week of each new month, like the terminal header
+    let mut last_month = grid_start.month();
+    for week in 0..weeks {
+        let date = grid_start + Duration::days(week * 7);
+        if date.month() != last_month {
+            let _ = writeln!(
+                svg,
+                "  <text x=\"{}\" y=\"{}\" {FONT}>{}</text>",
+                LEFT + week * STEP,
+                TOP - 6,
+                date.format("%b")
+            );
+            last_month = date.month();
+        }
+    }
+
+    for (row, label) in [(1, "Mon"), (3, "Wed"), (5, "Fri")] {
+        let _ = writeln!(
+            svg,
+            "  <text x=\"0\" y=\"{}\" {FONT}>{}</text>",
+            TOP + row * STEP + CELL - 2,
+            label
+        );
+    }
+
+    for week in 0..weeks {
+        for row in 0..7 {
+            let date = grid_start + Duration::days(week * 7 + row);
+            if date > end {
+                break;
+            }
+            let count = commits.get(&date).copied().unwrap_or(0);
+            cell(
+                &mut svg,
+                LEFT + week * STEP,
+                TOP + row * STEP,
+                Level::of(count),
+                &format!(
+                    "{} on {}",
+                    commits_label(count),
+                    date.format("%a, %b %-d, %Y")
+                ),
+            );
+        }
+    }
+
+    // Legend in the bottom right corner
+    let y = TOP + 7 * STEP + 8;
+    let mut x = width - (Level::ALL.len() as i64) * STEP - 30;
+    let _ = writeln!(
+        svg,
+        "  <text x=\"{}\" y=\"{}\" text-anchor=\"end\" {FONT}>Less</text>",
+        x - 4,
+        y + CELL - 2
+    );
+    for level in Level::ALL {
+        cell(&mut svg, x, y, level, &format!("{} commits", level.range()));
+        x += STEP;
+    }
+    let _ = writeln!(
+        svg,
+        "  <text x=\"{}\" y=\"{}\" {FONT}>More</text>",
+        x + 1,
+        y + CELL - 2
+    );
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, d).unwrap()
+    }
+
+    #[test]
+    fn test_render() {
+        let commits: CommitCounts = [(day(2), 1), (day(3), 7), (day(4), 12)]
+            .into_iter()
+            .collect();
+        // Sun Feb 22 through Wed Mar 4: two weeks, the second one cut short
+        let svg = render(
+            &commits,
+            NaiveDate::from_ymd_opt(2026, 2, 24).unwrap(),
+            day(4),
+        );
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>\n"));
+        // 11 days from the start of the first week, plus the legend
+        assert_eq!(svg.matches("<rect x=").count(), 11 + 4);
+        assert!(svg.contains(">Mar</text>"));
+        assert!(svg.contains(">Wed</text>"));
+        assert!(svg.contains("<title>1 commit on Mon, Mar 2, 2026</title>"));
+        assert!(svg.contains(&format!(
+            "fill=\"{}\" stroke=\"#d0d7de\" stroke-width=\"0.5\"><title>7 commits on Tue, Mar 3, 2026",
+            fill(Level::Medium)
+        )));
+        assert!(svg.contains(&format!(
+            "fill=\"{}\" stroke=\"#d0d7de\" stroke-width=\"0.5\"><title>12 commits on Wed, Mar 4, 2026",
+            fill(Level::High)
+        )));
+        assert!(svg.contains("<title>0 commits on Thu, Feb 26, 2026</title>"));
+        assert!(svg.contains("<title>10+ commits</title>"));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::stats::CommitCounts;
+use crate::summary::{self, Summary};
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+/// Rules merging several emails into one identity, written as
+/// comma-separated `alias=identity` pairs, e.g. `a@home.com=alice@work.com`.
+#[derive(Debug, Default)]
+pub struct Identities {
+    aliases: HashMap<String, String>,
+}
+
+impl Identities {
+    pub fn parse(rules: &str) -> Result<Self> {
+        let mut aliases = HashMap::new();
+        for rule in rules.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            let (alias, identity) = rule
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Identity rule '{}' must look like alias=identity", rule))?;
+            aliases.insert(alias.trim().to_lowercase(), identity.trim().to_string());
+        }
+        Ok(Identities { aliases })
+    }
+
+    /// The identity `email` is merged into, or the email itself.
+    pub fn resolve<'a>(&'a self, email: &'a str) -> &'a str {
+        self.aliases
+            .get(&email.to_lowercase())
+            .map_or(email, String::as_str)
+    }
+}
+
+/// One person on the leaderboard.
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub identity: String,
+    pub commits: CommitCounts,
+    pub summary: Summary,
+}
+
+/// Merge authors into identities and rank them by contributions between
+/// `start` and `end`, then by active days.
+pub fn leaderboard(
+    by_author: &HashMap<String, CommitCounts>,
+    identities: &Identities,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Vec<Member> {
+    let mut merged: HashMap<&str, CommitCounts> = HashMap::new();
+    for (email, commits) in by_author {
+        let counts = merged.entry(identities.resolve(email)).or_default();
+        for (date, n) in commits {
+            *counts.entry(*date).or_insert(0) += n;
+        }
+    }
+
+    let mut members: Vec<Member> = merged
+        .into_iter()
+        .map(|(identity, commits)| Member {
+            identity: identity.to_string(),
+            summary: summary::summarize(&commits, start, end),
+            commits,
+        })
+        .filter(|member| member.summary.total > 0)
+        .collect();
+    members.sort_by(|a, b| {
+        b.summary
+            .total
+            .cmp(&a.summary.total)
+            .then(b.summary.active_days.cmp(&a.summary.active_days))
+            .then(a.identity.cmp(&b.identity))
+    });
+    members
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, d).unwrap()
+    }
+
+    fn counts(days: &[(u32, i32)]) -> CommitCounts {
+        days.iter().map(|(d, n)| (day(*d), *n)).collect()
+    }
+
+    #[test]
+    fn test_identities() {
+        let identities =
+            Identities::parse("Alice@Home.com = alice@work.com, old@bob.com=Bob").unwrap();
+        assert_eq!(identities.resolve("alice@home.com"), "alice@work.com");
+        assert_eq!(identities.resolve("old@bob.com"), "Bob");
+        assert_eq!(identities.resolve("carol@work.com"), "carol@work.com");
+
+        assert!(Identities::parse("no-separator").is_err());
+        assert!(Identities::parse("").unwrap().aliases.is_empty());
+    }
+
+    #[test]
+    fn test_leaderboard_merges_identities() {
+        let by_author: HashMap<String, CommitCounts> = [
+            ("alice@work.com".to_string(), counts(&[(2, 1), (3, 1)])),
+            ("alice@home.com".to_string(), counts(&[(3, 2), (4, 1)])),
+            ("bob@work.com".to_string(), counts(&[(2, 3), (9, 2)])),
+            ("carol@work.com".to_string(), counts(&[(5, 5)])),
+        ]
+        .into_iter()
+        .collect();
+        let identities = Identities::parse("alice@home.com=alice@work.com").unwrap();
+
+        let members = leaderboard(&by_author, &identities, day(1), day(10));
+        let ranking: Vec<(&str, i32)> = members
+            .iter()
+            .map(|m| (m.identity.as_str(), m.summary.total))
+            .collect();
+        // Ties on commits go to whoever was active on more days
+        assert_eq!(
+            ranking,
+            vec![
+                ("alice@work.com", 5),
+                ("bob@work.com", 5),
+                ("carol@work.com", 5)
+            ]
+        );
+        assert_eq!(members[0].commits.get(&day(3)), Some(&3));
+        assert_eq!(members[0].summary.longest_streak.map(|s| s.days), Some(3));
+    }
+}
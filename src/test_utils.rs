@@ -0,0 +1,52 @@
+//! Helpers for the tests that set up Git repositories with the `git` CLI.
+
+use std::path::Path;
+use std::process::Command;
+
+fn run(mut command: Command, args: &[&str]) {
+    let output = command.args(args).output().unwrap();
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Run git in `repo_path`, failing the test if it fails.
+pub fn git(repo_path: &Path, args: &[&str]) {
+    let mut command = Command::new("git");
+    command.current_dir(repo_path);
+    run(command, args);
+}
+
+/// Like [`git`], with `date` (RFC 3339) as the author and committer date.
+pub fn git_at(repo_path: &Path, args: &[&str], date: &str) {
+    let mut command = Command::new("git");
+    command
+        .current_dir(repo_path)
+        .env("GIT_AUTHOR_DATE", date)
+        .env("GIT_COMMITTER_DATE", date);
+    run(command, args);
+}
+
+/// Commit as `email` in an existing repository, without signing.
+pub fn configure(repo_path: &Path, email: &str) {
+    git(repo_path, &["config", "user.email", email]);
+    git(repo_path, &["config", "user.name", "Test User"]);
+    git(repo_path, &["config", "commit.gpgsign", "false"]);
+}
+
+/// Create an empty repository on `main` committing as `email`.
+pub fn init_repo(repo_path: &Path, email: &str) {
+    std::fs::create_dir_all(repo_path).unwrap();
+    git(repo_path, &["init", "-b", "main"]);
+    configure(repo_path, email);
+}
+
+/// Write `content` to `file` and commit it, with the file name as the message.
+pub fn commit_file(repo_path: &Path, file: &str, content: &str) {
+    std::fs::write(repo_path.join(file), content).unwrap();
+    git(repo_path, &["add", file]);
+    git(repo_path, &["commit", "-m", file]);
+}
@@ -1,15 +1,31 @@
-use crate::stats::CommitCounts;
-use chrono::{Datelike, Duration, Local};
+use crate::compare::PeriodStats;
+use crate::orphans::RepoOrphans;
+use crate::reflog::Event;
+use crate::stale::StaleRepo;
+use crate::stats::{
+    CommitCounts, LanguageCounts, Punchcard, RepoFailure, RepoProfile, SkippedRepo,
+};
+use crate::status::RepoStatus;
+use crate::summary::Summary;
+use crate::team::Member;
+use crate::unpushed::{RepoBranches, Upstream};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate};
 use colored::Colorize;
+use std::collections::HashMap;
 
 pub fn print_stats(commits: &CommitCounts) {
     let now = Local::now().date_naive();
     let days_to_show = 183; // 6 months approx
 
-    // Align start to the Sunday before or on the 6-months-ago mark
-    let start_target = now - Duration::days(days_to_show);
-    let days_from_sun = start_target.weekday().num_days_from_sunday();
-    let grid_start = start_target - Duration::days(days_from_sun as i64);
+    print_grid(commits, week_start(now - Duration::days(days_to_show)), now);
+}
+
+/// Print the contribution grid for the weeks from `start_target` through `now`,
+/// leaving the days before `start_target` in its first week blank.
+fn print_grid(commits: &CommitCounts, start_target: NaiveDate, now: NaiveDate) {
+    let today = Local::now().date_naive();
+
+    let grid_start = week_start(start_target);
 
     let total_days = (now - grid_start).num_days() + 1;
     let total_weeks = (total_days as f64 / 7.0).ceil() as i64;
@@ -38,14 +54,55 @@ pub fn print_stats(commits: &CommitCounts) {
                 // If we break here, the rest of the row is empty.
                 break;
             }
+            if current_date < start_target {
+                print!("    "); // Width of a cell
+                continue;
+            }
 
             let count = commits.get(&current_date).unwrap_or(&0);
-            print_cell(*count, current_date == now);
+            print_cell(*count, current_date == today);
         }
         println!();
     }
 }
 
+/// The Sunday on or before `date`, where its column of the grid starts.
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_sunday() as i64)
+}
+
+/// How busy a day was, which sets its color in the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Empty,
+    Low,
+    Medium,
+    High,
+}
+
+impl Level {
+    pub const ALL: [Level; 4] = [Level::Empty, Level::Low, Level::Medium, Level::High];
+
+    pub fn of(count: i32) -> Self {
+        match count {
+            ..=0 => Level::Empty,
+            1..=4 => Level::Low,
+            5..=9 => Level::Medium,
+            _ => Level::High,
+        }
+    }
+
+    /// The counts falling into this level.
+    pub fn range(&self) -> &'static str {
+        match self {
+            Level::Empty => "0",
+            Level::Low => "1–4",
+            Level::Medium => "5–9",
+            Level::High => "10+",
+        }
+    }
+}
+
 fn print_months_header(start_date: chrono::NaiveDate, weeks: i64) {
     print!("     "); // Padding for day labels
     let mut current_week = start_date;
@@ -75,7 +132,8 @@ fn print_cell(count: i32, is_today: bool) {
 
     // We will use Colored crate's closest.
 
-    let text = if count == 0 {
+    let level = Level::of(count);
+    let text = if level == Level::Empty {
         "  - ".dimmed().to_string()
     } else {
         let s = if count >= 10 {
@@ -88,13 +146,483 @@ fn print_cell(count: i32, is_today: bool) {
             // Magenta BG
             s.black().on_magenta().to_string()
         } else {
-            match count {
-                1..=4 => s.black().on_white().to_string(),
-                5..=9 => s.black().on_yellow().to_string(),
-                _ => s.black().on_green().to_string(),
+            match level {
+                Level::Empty | Level::Low => s.black().on_white().to_string(),
+                Level::Medium => s.black().on_yellow().to_string(),
+                Level::High => s.black().on_green().to_string(),
             }
         }
     };
 
     print!("{}", text);
 }
+
+pub fn print_comparison(commits: &CommitCounts, before: &PeriodStats, after: &PeriodStats) {
+    let label = |stats: &PeriodStats| {
+        format!(
+            "{} – {}",
+            stats.period.start.format("%b %-d, %Y"),
+            stats.period.end.format("%b %-d, %Y")
+        )
+    };
+
+    for stats in [before, after] {
+        println!("\n {}", label(stats).bold());
+        print_grid(commits, stats.period.start, stats.period.end);
+    }
+
+    let weekday = |stats: &PeriodStats| {
+        stats
+            .busiest_weekday
+            .map_or("-".to_string(), |(day, n)| format!("{} ({})", day, n))
+    };
+    let delta = |before: i64, after: i64| {
+        let change = after - before;
+        let text = if before == 0 {
+            format!("{:+}", change)
+        } else {
+            format!(
+                "{:+} ({:+.1}%)",
+                change,
+                change as f64 * 100.0 / before as f64
+            )
+        };
+        match change {
+            0 => text.normal(),
+            c if c > 0 => text.green(),
+            _ => text.red(),
+        }
+    };
+
+    let width = label(before)
+        .chars()
+        .count()
+        .max(label(after).chars().count());
+    println!();
+    println!(
+        " {:<16} {:<width$}  {:<width$}  {}",
+        "",
+        label(before).bold(),
+        label(after).bold(),
+        "Change".bold()
+    );
+    println!(
+        " {:<16} {:<width$}  {:<width$}  {}",
+        "Contributions:".bold(),
+        before.summary.total,
+        after.summary.total,
+        delta(before.summary.total.into(), after.summary.total.into())
+    );
+    println!(
+        " {:<16} {:<width$}  {:<width$}  {}",
+        "Active days:".bold(),
+        before.summary.active_days,
+        after.summary.active_days,
+        delta(before.summary.active_days, after.summary.active_days)
+    );
+    let day = |stats: &PeriodStats| stats.busiest_weekday.map(|(day, _)| day);
+    let weekday_change = match (day(before), day(after)) {
+        (None, None) => "-".to_string(),
+        (before, after) if before == after => "unchanged".to_string(),
+        (before, after) => {
+            let name =
+                |day: Option<chrono::Weekday>| day.map_or("-".to_string(), |d| d.to_string());
+            format!("{} → {}", name(before), name(after))
+        }
+    };
+    println!(
+        " {:<16} {:<width$}  {:<width$}  {}",
+        "Busiest weekday:".bold(),
+        weekday(before),
+        weekday(after),
+        weekday_change
+    );
+}
+
+pub fn print_punchcard(punchcard: &Punchcard) {
+    let max = punchcard.iter().flatten().copied().max().unwrap_or(0);
+
+    print!("     ");
+    for hour in (0..24).step_by(3) {
+        // Right-align with the 3-wide cells, then skip the next two hours
+        print!("{:>3}{:6}", format!("{:02}", hour), "");
+    }
+    println!();
+
+    for (day, label) in ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"]
+        .iter()
+        .enumerate()
+    {
+        print!(" {} ", label);
+        for count in punchcard[day] {
+            print_punch(count, max);
+        }
+        println!();
+    }
+}
+
+fn print_punch(count: i32, max: i32) {
+    if count == 0 {
+        print!("{}", "  ·".dimmed());
+        return;
+    }
+
+    // Shade relative to the busiest hour, since absolute counts vary wildly
+    let s = format!("{:>3}", count);
+    let text = match count * 4 / max.max(1) {
+        0 => s.black().on_white(),
+        1 | 2 => s.black().on_yellow(),
+        _ => s.black().on_green(),
+    };
+    print!("{}", text);
+}
+
+pub fn print_summary(summary: &Summary, co_authored: i32) {
+    let streak = |streak: Option<crate::summary::Streak>| match streak {
+        Some(s) if s.days > 1 => format!(
+            "{} days ({} – {})",
+            s.days,
+            s.start.format("%b %-d"),
+            s.end.format("%b %-d")
+        ),
+        Some(s) => format!("1 day ({})", s.start.format("%b %-d")),
+        None => "0 days".to_string(),
+    };
+
+    println!();
+    println!(
+        " {:<16} {}",
+        "Contributions:".bold(),
+        summary.total.to_string().cyan()
+    );
+    if co_authored > 0 {
+        println!(
+            " {:<16} {} authored, {} co-authored",
+            "Credit:".bold(),
+            summary.total - co_authored,
+            co_authored
+        );
+    }
+    println!(
+        " {:<16} {}",
+        "Current streak:".bold(),
+        streak(summary.current_streak)
+    );
+    println!(
+        " {:<16} {}",
+        "Longest streak:".bold(),
+        streak(summary.longest_streak)
+    );
+    if let Some((date, count)) = summary.busiest_day {
+        println!(
+            " {:<16} {} ({} commits)",
+            "Busiest day:".bold(),
+            date.format("%a %b %-d, %Y"),
+            count
+        );
+    }
+    println!(
+        " {:<16} {}/{} ({:.1}%)",
+        "Active days:".bold(),
+        summary.active_days,
+        summary.total_days,
+        summary.active_percentage()
+    );
+    println!(
+        " {:<16} {:.1}",
+        "Commits/day:".bold(),
+        summary.mean_per_active_day()
+    );
+}
+
+pub fn print_events(events: &HashMap<Event, i32>) {
+    let mut events: Vec<_> = events.iter().collect();
+    events.sort();
+    let breakdown: Vec<String> = events
+        .iter()
+        .map(|(event, n)| format!("{} {}", n, event.label()))
+        .collect();
+    println!(
+        " {:<16} {}",
+        "Reflog:".bold(),
+        if breakdown.is_empty() {
+            "no entries".to_string()
+        } else {
+            breakdown.join(", ")
+        }
+    );
+}
+
+pub fn print_languages(languages: &LanguageCounts) {
+    if languages.is_empty() {
+        println!("\nNo commits touched files in a known language.");
+        return;
+    }
+
+    let mut rows: Vec<_> = languages.iter().collect();
+    rows.sort_by(|a, b| b.1.commits.cmp(&a.1.commits).then(a.0.cmp(b.0)));
+
+    let width = rows.iter().map(|(lang, _)| lang.len()).max().unwrap_or(0);
+    println!();
+    println!(
+        " {:<width$}  {:>7}  {:>9}",
+        "Language".bold(),
+        "Commits".bold(),
+        "Lines".bold()
+    );
+    for (lang, count) in rows {
+        println!(
+            " {:<width$}  {:>7}  {:>9}",
+            lang, count.commits, count.lines
+        );
+    }
+}
+
+pub fn print_leaderboard(members: &[Member], heatmaps: bool) {
+    if members.is_empty() {
+        println!("\nNo commits found.");
+        return;
+    }
+
+    let width = members
+        .iter()
+        .map(|m| m.identity.len())
+        .max()
+        .unwrap_or(0)
+        .max("Author".len());
+    let streak = |streak: Option<crate::summary::Streak>| streak.map_or(0, |s| s.days);
+    println!();
+    println!(
+        " {:>3}  {:<width$}  {:>7}  {:>11}  {:>14}  {:>14}",
+        "#".bold(),
+        "Author".bold(),
+        "Commits".bold(),
+        "Active days".bold(),
+        "Current streak".bold(),
+        "Longest streak".bold()
+    );
+    for (rank, member) in members.iter().enumerate() {
+        println!(
+            " {:>3}  {:<width$}  {:>7}  {:>11}  {:>14}  {:>14}",
+            rank + 1,
+            member.identity,
+            member.summary.total,
+            member.summary.active_days,
+            streak(member.summary.current_streak),
+            streak(member.summary.longest_streak)
+        );
+    }
+
+    if heatmaps {
+        for member in members {
+            println!("\n {}", member.identity.bold());
+            print_stats(&member.commits);
+        }
+    }
+}
+
+pub fn print_orphans(repos: &[RepoOrphans]) {
+    if repos.is_empty() {
+        println!("\nNo lost commits found.");
+        return;
+    }
+
+    for repo in repos {
+        println!("\n {}", repo.path.display().to_string().bold());
+        for orphan in &repo.orphans {
+            println!(
+                "  {}  {}  {:<8}  {}",
+                orphan.id.to_hex_with_len(10).to_string().yellow(),
+                orphan.time.format("%Y-%m-%d %H:%M"),
+                orphan.source.label(),
+                orphan.subject
+            );
+        }
+    }
+}
+
+pub fn print_unpushed(repos: &[RepoBranches]) {
+    if repos.is_empty() {
+        println!("\nEverything is pushed.");
+        return;
+    }
+
+    for repo in repos {
+        println!("\n {}", repo.path.display().to_string().bold());
+        for branch in &repo.branches {
+            let upstream = match &branch.upstream {
+                Upstream::Missing => "no upstream".red().to_string(),
+                Upstream::Gone(name) => format!("{} is gone", name).red().to_string(),
+                Upstream::Tracking { name, behind: 0 } => name.clone(),
+                Upstream::Tracking { name, behind } => format!("{}, {} behind", name, behind),
+            };
+            println!(
+                "  {:<24}  {:>3} unpushed  ({})",
+                branch.name.cyan(),
+                branch.unpushed.to_string().yellow(),
+                upstream
+            );
+        }
+    }
+}
+
+pub fn print_stale(repos: &[StaleRepo], months: u32) {
+    if repos.is_empty() {
+        println!(
+            "\nNo repositories without commits in the last {} months.",
+            months
+        );
+        return;
+    }
+
+    println!(
+        "\n {:<10}  {:>9}  {:>9}  {:<8}  Repository",
+        "Last", "Checkout", "History", "Pushed"
+    );
+    for repo in repos {
+        let last_commit = repo.last_commit.map_or_else(
+            || "never".to_string(),
+            |time| time.format("%Y-%m-%d").to_string(),
+        );
+        let pushed = match repo.pushed {
+            Some(true) => "yes".green(),
+            Some(false) => "no".red(),
+            None => "-".normal(),
+        };
+        println!(
+            " {:<10}  {:>9}  {:>9}  {:<8}  {}",
+            last_commit,
+            format_size(repo.checkout_size),
+            format_size(repo.vcs_size),
+            pushed,
+            repo.path.display()
+        );
+    }
+
+    let total: u64 = repos.iter().map(|r| r.checkout_size + r.vcs_size).sum();
+    println!(
+        "\n{} stale repositories using {}.",
+        repos.len().to_string().yellow(),
+        format_size(total).bold()
+    );
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+pub fn print_status(dirty: &[RepoStatus], total: usize) {
+    for repo in dirty {
+        let mut details = Vec::new();
+        if let Some(operation) = repo.operation {
+            details.push(
+                format!("{} in progress", operation.label())
+                    .red()
+                    .to_string(),
+            );
+        }
+        if repo.changed > 0 {
+            details.push(format!("{} changed", repo.changed).yellow().to_string());
+        }
+        if repo.untracked > 0 {
+            details.push(format!("{} untracked", repo.untracked));
+        }
+        println!(
+            "\n {}  {}",
+            repo.path.display().to_string().bold(),
+            repo.branch.as_deref().unwrap_or("(detached)").cyan()
+        );
+        println!("  {}", details.join(", "));
+    }
+
+    if dirty.is_empty() {
+        println!("\nAll {} repositories are clean.", total);
+    } else {
+        println!(
+            "\n{} of {} repositories need attention.",
+            dirty.len().to_string().yellow(),
+            total
+        );
+    }
+}
+
+pub fn print_profile(profiles: &[RepoProfile], top: usize) {
+    if profiles.is_empty() {
+        return;
+    }
+
+    eprintln!("\nSlowest repositories:");
+    for profile in profiles.iter().take(top) {
+        let backend = if profile.failed {
+            "failed".red()
+        } else {
+            profile
+                .backend
+                .map_or("cached".dimmed(), |b| b.label().normal())
+        };
+        eprintln!(
+            "  {:>10}  {:>7} commits  {:<7}  {}",
+            format!("{:.2?}", profile.elapsed),
+            profile.visited,
+            backend,
+            profile.path.display()
+        );
+    }
+}
+
+pub fn print_failures(failures: &[RepoFailure], verbose: bool) {
+    if failures.is_empty() {
+        return;
+    }
+
+    eprintln!();
+    if verbose {
+        eprintln!(
+            "{}",
+            format!("{} repositories failed:", failures.len()).yellow()
+        );
+        for failure in failures {
+            eprintln!("  {}: {:#}", failure.path.display(), failure.error);
+        }
+    } else {
+        eprintln!(
+            "{}",
+            format!(
+                "{} repositories failed (use --verbose for details)",
+                failures.len()
+            )
+            .yellow()
+        );
+    }
+}
+
+pub fn print_skipped(skipped: &[SkippedRepo]) {
+    if skipped.is_empty() {
+        return;
+    }
+
+    let mut skipped: Vec<_> = skipped.iter().collect();
+    skipped.sort_by(|a, b| a.path.cmp(&b.path));
+
+    eprintln!();
+    eprintln!("{} inactive repositories skipped:", skipped.len());
+    for repo in skipped {
+        let last: DateTime<Local> = repo.last_activity.time.into();
+        eprintln!(
+            "  {}: last activity {} ({})",
+            repo.path.display(),
+            last.format("%Y-%m-%d"),
+            repo.last_activity.source.display()
+        );
+    }
+}
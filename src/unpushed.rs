@@ -0,0 +1,218 @@
+use crate::stats::{self, RepoFailure};
+use anyhow::{Result, anyhow};
+use gix::ObjectId;
+use gix::bstr::ByteSlice;
+use std::path::{Path, PathBuf};
+
+/// Where a local branch pushes to, as far as the local data knows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Upstream {
+    /// No upstream is configured.
+    Missing,
+    /// An upstream is configured, but its remote-tracking branch doesn't exist,
+    /// e.g. it was deleted on the remote and pruned.
+    Gone(String),
+    Tracking {
+        name: String,
+        behind: usize,
+    },
+}
+
+/// A local branch whose commits aren't safely on a remote.
+#[derive(Debug, Clone)]
+pub struct BranchReport {
+    pub name: String,
+    pub upstream: Upstream,
+    /// Commits by the queried identity that the upstream doesn't have or, without
+    /// one, that no remote-tracking branch has.
+    pub unpushed: usize,
+}
+
+#[derive(Debug)]
+pub struct RepoBranches {
+    pub path: PathBuf,
+    pub branches: Vec<BranchReport>,
+}
+
+/// Compare every local branch with its upstream, using only what was last
+/// fetched. Branches that are pushed and track an upstream are left out.
+pub fn find(repos: Vec<PathBuf>, email: &str) -> (Vec<RepoBranches>, Vec<RepoFailure>) {
+    stats::check_repos(repos, |path| {
+        if !path.join(".git").exists() {
+            return Ok(None);
+        }
+        let branches = find_in_repo(path, email)?;
+        Ok((!branches.is_empty()).then(|| RepoBranches {
+            path: path.to_path_buf(),
+            branches,
+        }))
+    })
+}
+
+fn find_in_repo(path: &Path, email: &str) -> Result<Vec<BranchReport>> {
+    let repo = gix::open(path)?;
+    let mut branches = branches(&repo, Some(email))?;
+    branches.retain(|branch| {
+        branch.unpushed > 0 || !matches!(branch.upstream, Upstream::Tracking { .. })
+    });
+    Ok(branches)
+}
+
+/// Whether every local branch's commits, by anyone, are on a remote.
+pub fn all_pushed(repo: &gix::Repository) -> Result<bool> {
+    Ok(branches(repo, None)?
+        .iter()
+        .all(|branch| branch.unpushed == 0))
+}
+
+/// Every local branch, counting unpushed commits by `email`, or by anyone.
+fn branches(repo: &gix::Repository, email: Option<&str>) -> Result<Vec<BranchReport>> {
+    let references = repo.references()?;
+
+    let mut remote_tips = Vec::new();
+    for reference in references.remote_branches()? {
+        let mut reference = reference.map_err(|e| anyhow!(e))?;
+        if let Ok(commit) = reference.peel_to_commit() {
+            remote_tips.push(commit.id);
+        }
+    }
+
+    let mut branches = Vec::new();
+    for reference in references.local_branches()? {
+        let mut reference = reference.map_err(|e| anyhow!(e))?;
+        let Ok(tip) = reference.peel_to_commit().map(|commit| commit.id) else {
+            continue;
+        };
+        let name = reference.name();
+
+        let tracking = repo
+            .branch_remote_tracking_ref_name(name, gix::remote::Direction::Fetch)
+            .transpose()?;
+        let upstream_tip = match &tracking {
+            Some(tracking) => repo
+                .try_find_reference(tracking.as_ref())?
+                .map(|mut upstream| upstream.peel_to_commit().map(|commit| commit.id))
+                .transpose()?,
+            None => None,
+        };
+
+        let (upstream, unpushed) = match (tracking, upstream_tip) {
+            (Some(tracking), Some(upstream_tip)) => (
+                Upstream::Tracking {
+                    name: tracking.shorten().to_str_lossy().into_owned(),
+                    behind: count(repo, upstream_tip, [tip], None)?,
+                },
+                count(repo, tip, [upstream_tip], email)?,
+            ),
+            (tracking, _) => (
+                tracking.map_or(Upstream::Missing, |tracking| {
+                    Upstream::Gone(tracking.shorten().to_str_lossy().into_owned())
+                }),
+                count(repo, tip, remote_tips.iter().copied(), email)?,
+            ),
+        };
+        branches.push(BranchReport {
+            name: name.shorten().to_str_lossy().into_owned(),
+            upstream,
+            unpushed,
+        });
+    }
+
+    Ok(branches)
+}
+
+/// Commits reachable from `tip` but not from `hidden`, only counting ones
+/// authored by `email` if given.
+fn count(
+    repo: &gix::Repository,
+    tip: ObjectId,
+    hidden: impl IntoIterator<Item = ObjectId>,
+    email: Option<&str>,
+) -> Result<usize> {
+    let mut n = 0;
+    for info in repo.rev_walk([tip]).with_hidden(hidden).all()? {
+        let info = info?;
+        if let Some(email) = email
+            && !info
+                .object()?
+                .author()?
+                .email
+                .eq_ignore_ascii_case(email.as_bytes())
+        {
+            continue;
+        }
+        n += 1;
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{configure, git, init_repo};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_find_unpushed_branches() {
+        let dir = tempdir().unwrap();
+        let email = "test@example.com";
+        let origin = dir.path().join("origin");
+        init_repo(&origin, email);
+        git(&origin, &["commit", "--allow-empty", "-m", "Initial"]);
+
+        let clone = dir.path().join("clone");
+        git(
+            dir.path(),
+            &["clone", origin.to_str().unwrap(), clone.to_str().unwrap()],
+        );
+        configure(&clone, email);
+
+        // Pushed and up to date, so not reported
+        git(&clone, &["branch", "--track", "pushed", "origin/main"]);
+        // Two unpushed commits of ours, one of someone else's, and one we're behind on
+        git(&clone, &["commit", "--allow-empty", "-m", "Mine"]);
+        git(&clone, &["commit", "--allow-empty", "-m", "Mine too"]);
+        git(
+            &clone,
+            &[
+                "-c",
+                "user.email=other@example.com",
+                "commit",
+                "--allow-empty",
+                "-m",
+                "Theirs",
+            ],
+        );
+        git(&origin, &["commit", "--allow-empty", "-m", "Upstream"]);
+        git(&clone, &["fetch"]);
+        // A local-only branch
+        git(
+            &clone,
+            &["checkout", "--no-track", "-b", "local", "origin/main"],
+        );
+        git(&clone, &["commit", "--allow-empty", "-m", "Local"]);
+
+        let (found, failures) = find(vec![clone.clone()], email);
+        assert!(failures.is_empty());
+        assert_eq!(found.len(), 1);
+        let branches: Vec<(&str, &Upstream, usize)> = found[0]
+            .branches
+            .iter()
+            .map(|b| (b.name.as_str(), &b.upstream, b.unpushed))
+            .collect();
+        assert_eq!(
+            branches,
+            vec![
+                ("local", &Upstream::Missing, 1),
+                (
+                    "main",
+                    &Upstream::Tracking {
+                        name: "origin/main".to_string(),
+                        behind: 1
+                    },
+                    2
+                ),
+            ]
+        );
+    }
+}
@@ -17,26 +17,26 @@ fn test_cli_integration() -> Result<(), Box<dyn std::error::Error>> {
         .current_dir(&repo_path)
         .output()?;
     StdCommand::new("git")
-        .args(&["config", "user.email", email])
+        .args(["config", "user.email", email])
         .current_dir(&repo_path)
         .output()?;
     StdCommand::new("git")
-        .args(&["config", "user.name", "Test User"])
+        .args(["config", "user.name", "Test User"])
         .current_dir(&repo_path)
         .output()?;
 
     StdCommand::new("git")
-        .args(&["config", "commit.gpgsign", "false"])
+        .args(["config", "commit.gpgsign", "false"])
         .current_dir(&repo_path)
         .output()?;
 
     std::fs::write(repo_path.join("README.md"), "# Test")?;
     StdCommand::new("git")
-        .args(&["add", "."])
+        .args(["add", "."])
         .current_dir(&repo_path)
         .output()?;
     StdCommand::new("git")
-        .args(&["commit", "-m", "Initial commit"])
+        .args(["commit", "-m", "Initial commit"])
         .current_dir(&repo_path)
         .output()?;
 
@@ -57,3 +57,168 @@ fn test_cli_integration() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_strict_fails_on_broken_repo() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    std::fs::create_dir_all(dir.path().join("broken").join(".git"))?;
+
+    Command::new(cargo::cargo_bin!("gitlocalstats"))
+        .arg("--folder")
+        .arg(dir.path())
+        .arg("--email")
+        .arg("integration@test.com")
+        .arg("--rescan")
+        .env("HOME", dir.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("1 repositories failed"));
+
+    Command::new(cargo::cargo_bin!("gitlocalstats"))
+        .arg("--folder")
+        .arg(dir.path())
+        .arg("--email")
+        .arg("integration@test.com")
+        .arg("--rescan")
+        .arg("--strict")
+        .arg("--verbose")
+        .env("HOME", dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("broken"));
+
+    Ok(())
+}
+
+#[test]
+fn test_team_leaderboard() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let repo_path = dir.path().join("shared");
+    std::fs::create_dir(&repo_path)?;
+
+    let git = |args: &[&str]| {
+        StdCommand::new("git")
+            .args(args)
+            .current_dir(&repo_path)
+            .output()
+    };
+    git(&["init"])?;
+    git(&["config", "user.name", "Test User"])?;
+    git(&["config", "commit.gpgsign", "false"])?;
+    for (email, message) in [
+        ("alice@work.com", "One"),
+        ("alice@home.com", "Two"),
+        (
+            "bob@work.com",
+            "Pair\n\nCo-authored-by: Carol <carol@work.com>",
+        ),
+    ] {
+        git(&["config", "user.email", email])?;
+        git(&["commit", "--allow-empty", "-m", message])?;
+    }
+
+    let config_dir = dir.path().join(".config").join("gitlocalstats");
+    std::fs::create_dir_all(&config_dir)?;
+    std::fs::write(
+        config_dir.join("config"),
+        "identities=alice@home.com=Alice,alice@work.com=Alice\n",
+    )?;
+
+    Command::new(cargo::cargo_bin!("gitlocalstats"))
+        .arg("team")
+        .arg("--folder")
+        .arg(dir.path())
+        .arg("--rescan")
+        .env("HOME", dir.path())
+        // Today's commits have to fall inside the window west of UTC too
+        .env("TZ", "Etc/GMT+12")
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(r"1\s+Alice\s+2")?
+                .and(predicate::str::contains("bob@work.com"))
+                .and(predicate::str::contains("carol@work.com")),
+        );
+
+    Ok(())
+}
+
+#[test]
+fn test_csv_output_keeps_stdout_clean() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let repo_path = dir.path().join("repo");
+    std::fs::create_dir(&repo_path)?;
+
+    let email = "integration@test.com";
+    let git = |args: &[&str]| {
+        StdCommand::new("git")
+            .args(args)
+            .current_dir(&repo_path)
+            .output()
+    };
+    git(&["init"])?;
+    git(&["config", "user.email", email])?;
+    git(&["config", "user.name", "Test User"])?;
+    git(&["config", "commit.gpgsign", "false"])?;
+    git(&["commit", "--allow-empty", "-m", "Initial commit"])?;
+
+    // Far west of UTC, where today's commits used to land past the end of the window
+    let offset = chrono::FixedOffset::west_opt(12 * 3600).unwrap();
+    let today = chrono::Utc::now().with_timezone(&offset).date_naive();
+    Command::new(cargo::cargo_bin!("gitlocalstats"))
+        .arg("--folder")
+        .arg(dir.path())
+        .arg("--email")
+        .arg(email)
+        .arg("--rescan")
+        .arg("--verbose")
+        .arg("--format")
+        .arg("csv")
+        .env("HOME", dir.path())
+        .env("TZ", "Etc/GMT+12")
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::starts_with("date,commits\n")
+                .and(predicate::str::ends_with(format!("{},1\n", today)))
+                .and(predicate::str::contains("Scanning").not()),
+        )
+        .stderr(predicate::str::contains("Scanning").and(predicate::str::contains("Done in")));
+
+    Ok(())
+}
+
+#[test]
+fn test_commit_filters_rejected_by_other_subcommands() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+
+    Command::new(cargo::cargo_bin!("gitlocalstats"))
+        .arg("status")
+        .arg("--folder")
+        .arg(dir.path())
+        .arg("--grep")
+        .arg("WIP")
+        .env("HOME", dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--grep can't be combined with status",
+        ));
+
+    Command::new(cargo::cargo_bin!("gitlocalstats"))
+        .arg("--lang")
+        .arg("rust")
+        .arg("--compare")
+        .arg("previous")
+        .arg("status")
+        .arg("--folder")
+        .arg(dir.path())
+        .env("HOME", dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--lang, --compare can't be combined with status",
+        ));
+
+    Ok(())
+}